@@ -0,0 +1,33 @@
+// Benchmarks the parsing and both parts of every day, on every input found in its directory.
+//
+// Each input is a group named `dayNN/INPUT` with the functions `parse`, `part1` and `part2`, so
+// `cargo bench --bench days -- day12/` only measures day 12.
+
+use advent_of_code::registry::{inputs, Measure, SOLUTIONS};
+use criterion::measurement::WallTime;
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, Criterion};
+
+/// Measures every stage as a function of the group.
+struct Bench<'a, 'b>(&'b mut BenchmarkGroup<'a, WallTime>);
+
+impl Measure for Bench<'_, '_> {
+    fn measure(&mut self, stage: &str, run: &mut dyn FnMut()) {
+        self.0
+            .bench_function(stage, |bencher| bencher.iter(&mut *run));
+    }
+}
+
+fn days(c: &mut Criterion) {
+    for solution in &SOLUTIONS {
+        for input in inputs(solution.day) {
+            let mut group = c.benchmark_group(format!("day{:0>2}/{}", solution.day, input.name));
+            if let Err(error) = (solution.run)(&input, &mut Bench(&mut group)) {
+                eprintln!("day {}, {}: {error}", solution.day, input.name);
+            }
+            group.finish();
+        }
+    }
+}
+
+criterion_group!(benches, days);
+criterion_main!(benches);
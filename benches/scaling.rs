@@ -0,0 +1,64 @@
+// Benchmarks the days whose running time depends most on the size of the input, on generated
+// inputs of increasing size.
+//
+// Each day is a group named `dayNN/scaling`, with the functions `parse/SIZE`, `part1/SIZE` and
+// `part2/SIZE`, so the reports plot every stage against the size.
+
+use advent_of_code::generate::GENERATORS;
+use advent_of_code::random::Random;
+use advent_of_code::registry::{Input, Measure, SOLUTIONS};
+use criterion::measurement::WallTime;
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
+
+const SCALED_DAYS: [(u8, [usize; 4]); 5] = [
+    (5, [1_000, 10_000, 100_000, 1_000_000]),
+    (8, [12, 25, 50, 100]),
+    (12, [16, 32, 64, 128]),
+    (14, [10, 40, 160, 640]),
+    (15, [10, 20, 40, 80]),
+];
+
+const LARGE_SIZE: usize = 100_000;
+
+/// Measures every stage as a function of the group, with the size as its parameter.
+struct Bench<'a, 'b> {
+    group: &'b mut BenchmarkGroup<'a, WallTime>,
+    size: usize,
+}
+
+impl Measure for Bench<'_, '_> {
+    fn measure(&mut self, stage: &str, run: &mut dyn FnMut()) {
+        self.group
+            .bench_function(BenchmarkId::new(stage, self.size), |bencher| {
+                bencher.iter(&mut *run)
+            });
+    }
+}
+
+fn scaling(c: &mut Criterion) {
+    for (day, sizes) in SCALED_DAYS {
+        let solution = &SOLUTIONS[day as usize - 1];
+        let generator = &GENERATORS[day as usize - 1];
+        let mut group = c.benchmark_group(format!("day{day:0>2}/scaling"));
+        for size in sizes {
+            // The largest inputs take seconds to run, so they are measured fewer times.
+            group.sample_size(if size >= LARGE_SIZE { 10 } else { 100 });
+            let input = Input::new(
+                size.to_string(),
+                (generator.generate)(size, &mut Random::new(size as u64)),
+                false,
+            );
+            let mut bench = Bench {
+                group: &mut group,
+                size,
+            };
+            if let Err(error) = (solution.run)(&input, &mut bench) {
+                eprintln!("day {day}, size {size}: {error}");
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, scaling);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+#![no_main]
+
+use advent_of_code::day02;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = day02::part1(input);
+    let _ = day02::part2(input);
+});
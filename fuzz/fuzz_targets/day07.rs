@@ -0,0 +1,9 @@
+#![no_main]
+
+use advent_of_code::day07;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = day07::part1(input);
+    let _ = day07::part2(input);
+});
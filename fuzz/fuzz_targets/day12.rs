@@ -0,0 +1,9 @@
+#![no_main]
+
+use advent_of_code::day12;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = day12::part1(input);
+    let _ = day12::part2(input);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use advent_of_code::day15;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = day15::part1(input, 10);
+    let _ = day15::part2(input, 20);
+});
@@ -0,0 +1,207 @@
+// An arbitrary-precision unsigned integer, used by the puzzles whose values grow without bound.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A non-negative integer stored as base 2^32 digits, with the least significant digit first and
+/// without leading zeros.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    digits: Vec<u32>,
+}
+
+impl BigUint {
+    fn from_digits(mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        BigUint { digits }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    fn bits(&self) -> usize {
+        match self.digits.last() {
+            Some(last) => self.digits.len() * 32 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.digits[index / 32] >> (index % 32) & 1 == 1
+    }
+
+    /// Returns `self * 2 + bit`.
+    fn shift_in(&self, bit: bool) -> Self {
+        let mut digits = Vec::with_capacity(self.digits.len() + 1);
+        let mut carry = bit as u32;
+        for digit in &self.digits {
+            digits.push(digit << 1 | carry);
+            carry = digit >> 31;
+        }
+        digits.push(carry);
+        BigUint::from_digits(digits)
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let mut digits = Vec::with_capacity(self.digits.len().max(other.digits.len()) + 1);
+        let mut carry = 0_u64;
+        for i in 0..self.digits.len().max(other.digits.len()) {
+            let sum = carry
+                + *self.digits.get(i).unwrap_or(&0) as u64
+                + *other.digits.get(i).unwrap_or(&0) as u64;
+            digits.push(sum as u32);
+            carry = sum >> 32;
+        }
+        digits.push(carry as u32);
+        BigUint::from_digits(digits)
+    }
+
+    /// Returns `None` if the result would be negative.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if *self < *other {
+            return None;
+        }
+
+        let mut digits = Vec::with_capacity(self.digits.len());
+        let mut borrow = 0_i64;
+        for i in 0..self.digits.len() {
+            let mut difference =
+                self.digits[i] as i64 - *other.digits.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += 1 << 32;
+                borrow = 1;
+            }
+            digits.push(difference as u32);
+        }
+        Some(BigUint::from_digits(digits))
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut digits = vec![0_u32; self.digits.len() + other.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0_u64;
+            for (j, &b) in other.digits.iter().enumerate() {
+                // (2^32 - 1)^2 + 2 * (2^32 - 1) still fits in 64 bits.
+                let product = a as u64 * b as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            digits[i + other.digits.len()] = carry as u32;
+        }
+        BigUint::from_digits(digits)
+    }
+
+    /// Returns the quotient and the remainder, or `None` when dividing by zero.
+    pub fn checked_div_rem(&self, divisor: &Self) -> Option<(Self, Self)> {
+        match divisor.digits[..] {
+            [] => None,
+            [divisor] => {
+                let (quotient, remainder) = self.div_rem_digit(divisor);
+                Some((quotient, BigUint::from(remainder as u64)))
+            }
+            _ => {
+                // Binary long division, good enough for the occasional large divisor.
+                let mut quotient = vec![0_u32; self.digits.len()];
+                let mut remainder = BigUint::default();
+                for i in (0..self.bits()).rev() {
+                    remainder = remainder.shift_in(self.bit(i));
+                    if remainder >= *divisor {
+                        remainder = remainder.checked_sub(divisor).unwrap();
+                        quotient[i / 32] |= 1 << (i % 32);
+                    }
+                }
+                Some((BigUint::from_digits(quotient), remainder))
+            }
+        }
+    }
+
+    fn div_rem_digit(&self, divisor: u32) -> (Self, u32) {
+        let mut quotient = vec![0_u32; self.digits.len()];
+        let mut remainder = 0_u64;
+        for i in (0..self.digits.len()).rev() {
+            let current = remainder << 32 | self.digits[i] as u64;
+            quotient[i] = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        (BigUint::from_digits(quotient), remainder as u32)
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        BigUint::from_digits(vec![value as u32, (value >> 32) as u32])
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.digits
+            .len()
+            .cmp(&other.digits.len())
+            .then_with(|| self.digits.iter().rev().cmp(other.digits.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const CHUNK: u32 = 1_000_000_000;
+
+        let mut chunks = vec![];
+        let mut value = self.clone();
+        while !value.is_zero() {
+            let (quotient, remainder) = value.div_rem_digit(CHUNK);
+            chunks.push(remainder);
+            value = quotient;
+        }
+
+        match chunks.pop() {
+            Some(first) => write!(f, "{first}")?,
+            None => write!(f, "0")?,
+        }
+        for chunk in chunks.iter().rev() {
+            write!(f, "{chunk:09}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_big_uint() {
+    let a = BigUint::from(u64::MAX);
+    let b = BigUint::from(12345);
+
+    let square = a.mul(&a);
+    assert_eq!(
+        square.to_string(),
+        "340282366920938463426481119284349108225"
+    );
+    assert_eq!(a.add(&b).to_string(), "18446744073709563960");
+    assert_eq!(b.checked_sub(&a), None);
+    assert_eq!(a.checked_sub(&b), Some(BigUint::from(u64::MAX - 12345)));
+
+    assert_eq!(
+        square.checked_div_rem(&a),
+        Some((a.clone(), BigUint::default()))
+    );
+    let (quotient, remainder) = square.add(&b).checked_div_rem(&a.add(&b)).unwrap();
+    assert_eq!(quotient.mul(&a.add(&b)).add(&remainder), square.add(&b));
+    assert!(remainder < a.add(&b));
+    assert_eq!(
+        square.checked_div_rem(&BigUint::from(10)).unwrap().1,
+        BigUint::from(5)
+    );
+    assert_eq!(a.checked_div_rem(&BigUint::default()), None);
+
+    assert_eq!(BigUint::default().to_string(), "0");
+    assert!(BigUint::from(1 << 40) > BigUint::from(u32::MAX as u64));
+}
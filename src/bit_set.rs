@@ -0,0 +1,206 @@
+// Sets of small integers and of ASCII letters stored in a single machine word.
+
+/// A set of integers from 0 to 63.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BitSet64(u64);
+
+impl BitSet64 {
+    pub const CAPACITY: usize = 64;
+
+    pub fn new() -> Self {
+        BitSet64(0)
+    }
+
+    fn mask(value: usize) -> u64 {
+        assert!(
+            value < Self::CAPACITY,
+            "{value} does not fit in a BitSet64."
+        );
+        1 << value
+    }
+
+    /// Returns whether the value was not in the set.
+    pub fn insert(&mut self, value: usize) -> bool {
+        let inserted = !self.contains(value);
+        self.0 |= Self::mask(value);
+        inserted
+    }
+
+    /// Returns whether the value was in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        let removed = self.contains(value);
+        self.0 &= !Self::mask(value);
+        removed
+    }
+
+    /// Inserts the value if it is not in the set, removes it otherwise.
+    pub fn toggle(&mut self, value: usize) {
+        self.0 ^= Self::mask(value);
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        value < Self::CAPACITY && self.0 >> value & 1 == 1
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        BitSet64(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        BitSet64(self.0 & other.0)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        BitSet64(self.0 & !other.0)
+    }
+
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        BitSet64(self.0 ^ other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterates over the values in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let value = bits.trailing_zeros() as usize;
+            // Clear the lowest set bit.
+            bits &= bits - 1;
+            Some(value)
+        })
+    }
+}
+
+impl FromIterator<usize> for BitSet64 {
+    fn from_iter<I: IntoIterator<Item = usize>>(values: I) -> Self {
+        let mut set = BitSet64::new();
+        for value in values {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+/// A set of ASCII letters. The lowercase letters come before the uppercase ones, so the index of
+/// a letter is its priority in day 3 minus one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CharSet(BitSet64);
+
+impl CharSet {
+    pub fn new() -> Self {
+        CharSet(BitSet64::new())
+    }
+
+    /// Returns the index of the letter, or `None` if it is not an ASCII letter.
+    pub fn index(letter: char) -> Option<usize> {
+        match letter {
+            'a'..='z' => Some(letter as usize - 'a' as usize),
+            'A'..='Z' => Some(letter as usize - 'A' as usize + 26),
+            _ => None,
+        }
+    }
+
+    fn letter(index: usize) -> char {
+        match index {
+            0..=25 => (b'a' + index as u8) as char,
+            _ => (b'A' + (index - 26) as u8) as char,
+        }
+    }
+
+    fn checked_index(letter: char) -> usize {
+        Self::index(letter).unwrap_or_else(|| panic!("{letter:?} is not an ASCII letter."))
+    }
+
+    /// Returns whether the letter was not in the set. Panics if it is not an ASCII letter.
+    pub fn insert(&mut self, letter: char) -> bool {
+        self.0.insert(Self::checked_index(letter))
+    }
+
+    /// Returns whether the letter was in the set.
+    pub fn remove(&mut self, letter: char) -> bool {
+        Self::index(letter).is_some_and(|index| self.0.remove(index))
+    }
+
+    /// Inserts the letter if it is not in the set, removes it otherwise. Panics if it is not an
+    /// ASCII letter.
+    pub fn toggle(&mut self, letter: char) {
+        self.0.toggle(Self::checked_index(letter));
+    }
+
+    pub fn contains(&self, letter: char) -> bool {
+        Self::index(letter).is_some_and(|index| self.0.contains(index))
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        CharSet(self.0.union(&other.0))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        CharSet(self.0.intersection(&other.0))
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        CharSet(self.0.difference(&other.0))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the letters, the lowercase ones first.
+    pub fn iter(&self) -> impl Iterator<Item = char> {
+        self.0.iter().map(Self::letter)
+    }
+}
+
+impl FromIterator<char> for CharSet {
+    fn from_iter<I: IntoIterator<Item = char>>(letters: I) -> Self {
+        let mut set = CharSet::new();
+        for letter in letters {
+            set.insert(letter);
+        }
+        set
+    }
+}
+
+#[test]
+fn test_bit_set() {
+    let mut set: BitSet64 = [1, 5, 63].into_iter().collect();
+    assert!(set.insert(0));
+    assert!(!set.insert(5));
+    assert!(set.remove(1));
+    assert!(!set.contains(64));
+    assert_eq!(set.iter().collect::<Vec<_>>(), [0, 5, 63]);
+
+    let other: BitSet64 = [5, 6].into_iter().collect();
+    assert_eq!(set.union(&other).len(), 4);
+    assert_eq!(set.intersection(&other).iter().collect::<Vec<_>>(), [5]);
+    assert_eq!(set.difference(&other).iter().collect::<Vec<_>>(), [0, 63]);
+    assert_eq!(set.symmetric_difference(&other).len(), 3);
+
+    set.toggle(5);
+    set.toggle(7);
+    assert_eq!(set.iter().collect::<Vec<_>>(), [0, 7, 63]);
+    assert!(BitSet64::new().is_empty());
+
+    let letters: CharSet = "zaZAa".chars().collect();
+    assert_eq!(letters.len(), 4);
+    assert_eq!(letters.iter().collect::<String>(), "azAZ");
+    assert!(letters.contains('Z') && !letters.contains('b') && !letters.contains('!'));
+    assert_eq!(CharSet::index('A'), Some(26));
+    assert_eq!(CharSet::index('1'), None);
+}
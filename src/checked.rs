@@ -0,0 +1,163 @@
+// Overflow-checked arithmetic for the values that can grow with the size of the input.
+//
+// Every operation returns an `OverflowError` naming the operation instead of wrapping. The days
+// whose values can grow without bound can use `BigUint` instead of a primitive type, which is
+// opt-in with the `--big-int` flag.
+
+use crate::big_uint::BigUint;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static BIG_INTEGERS: AtomicBool = AtomicBool::new(false);
+
+pub fn enable_big_integers() {
+    BIG_INTEGERS.store(true, Ordering::Relaxed);
+}
+
+pub fn big_integers_enabled() -> bool {
+    BIG_INTEGERS.load(Ordering::Relaxed)
+}
+
+/// An operation whose result does not fit in its type (or a division by zero).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverflowError {
+    pub operation: String,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "arithmetic overflow in `{}`", self.operation)
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+/// A number type with checked arithmetic.
+pub trait Number: Clone + Eq + fmt::Display {
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn checked_rem(&self, other: &Self) -> Option<Self>;
+    fn is_zero(&self) -> bool;
+}
+
+macro_rules! impl_number {
+    ($($type:ty),+) => {
+        $(impl Number for $type {
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_add(*self, *other)
+            }
+
+            fn checked_sub(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_sub(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_mul(*self, *other)
+            }
+
+            fn checked_div(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_div(*self, *other)
+            }
+
+            fn checked_rem(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_rem(*self, *other)
+            }
+
+            fn is_zero(&self) -> bool {
+                *self == 0
+            }
+        })+
+    };
+}
+
+impl_number!(i32, i64, u32, u64, usize);
+
+impl Number for BigUint {
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self.add(other))
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        BigUint::checked_sub(self, other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self.mul(other))
+    }
+
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        Some(self.checked_div_rem(other)?.0)
+    }
+
+    fn checked_rem(&self, other: &Self) -> Option<Self> {
+        Some(self.checked_div_rem(other)?.1)
+    }
+
+    fn is_zero(&self) -> bool {
+        BigUint::is_zero(self)
+    }
+}
+
+fn check<T: Number>(a: &T, operator: &str, b: &T, result: Option<T>) -> Result<T, OverflowError> {
+    result.ok_or_else(|| OverflowError {
+        operation: format!("{a} {operator} {b} ({})", std::any::type_name::<T>()),
+    })
+}
+
+pub fn add<T: Number>(a: T, b: T) -> Result<T, OverflowError> {
+    check(&a, "+", &b, a.checked_add(&b))
+}
+
+pub fn sub<T: Number>(a: T, b: T) -> Result<T, OverflowError> {
+    check(&a, "-", &b, a.checked_sub(&b))
+}
+
+pub fn mul<T: Number>(a: T, b: T) -> Result<T, OverflowError> {
+    check(&a, "*", &b, a.checked_mul(&b))
+}
+
+pub fn div<T: Number>(a: T, b: T) -> Result<T, OverflowError> {
+    check(&a, "/", &b, a.checked_div(&b))
+}
+
+pub fn rem<T: Number>(a: T, b: T) -> Result<T, OverflowError> {
+    check(&a, "%", &b, a.checked_rem(&b))
+}
+
+/// Converts between the number types, failing instead of truncating like `as`.
+pub fn convert<T: Copy + fmt::Display, U: TryFrom<T>>(value: T) -> Result<U, OverflowError> {
+    U::try_from(value).map_err(|_| OverflowError {
+        operation: format!(
+            "{value} as {} (from {})",
+            std::any::type_name::<U>(),
+            std::any::type_name::<T>()
+        ),
+    })
+}
+
+#[test]
+fn test_checked() {
+    assert_eq!(add(1_i32, 2), Ok(3));
+    assert_eq!(
+        mul(i64::MAX, 2).unwrap_err().to_string(),
+        "arithmetic overflow in `9223372036854775807 * 2 (i64)`"
+    );
+    assert_eq!(
+        sub(1_u32, 2).unwrap_err().operation,
+        "1 - 2 (u32)".to_string()
+    );
+    assert!(div(1_usize, 0).is_err());
+    assert_eq!(rem(7_u64, 4), Ok(3));
+
+    assert_eq!(convert::<i64, i32>(-5), Ok(-5));
+    assert_eq!(
+        convert::<i64, i32>(1 << 40).unwrap_err().operation,
+        "1099511627776 as i32 (from i64)"
+    );
+
+    let big = mul(BigUint::from(u64::MAX), BigUint::from(u64::MAX)).unwrap();
+    assert_eq!(rem(big, BigUint::from(10)), Ok(BigUint::from(5)));
+    assert!(sub(BigUint::from(1), BigUint::from(2)).is_err());
+}
@@ -1,59 +1,346 @@
 // https://adventofcode.com/2022/day/1
 
 use crate::get_input;
-use std::cmp;
-use std::str::FromStr;
+use crate::parse::{parse_field, ParseError};
+use crate::stream::{for_each_line, StreamError};
+#[cfg(test)]
+use proptest::prelude::*;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::io::BufRead;
 
 const DAY: u8 = 1;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
-}
-
-fn part1(input: &str) -> i32 {
-    let mut current = 0;
-    let mut max = 0;
-    for line in input.lines() {
-        if !line.is_empty() {
-            current += i32::from_str(line).unwrap_or(0);
-        } else {
-            max = cmp::max(current, max);
-            current = 0;
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
+}
+
+/// Returns the calories carried by every elf. The totals are kept in `i64`, so they cannot
+/// overflow.
+pub fn parse(input: &str) -> Result<Vec<i64>, ParseError> {
+    let mut elves = vec![];
+    match for_each_elf(input.as_bytes(), |elf| elves.push(elf.calories)) {
+        Ok(()) => Ok(elves),
+        Err(StreamError::Parse(error)) => Err(error),
+        // A string is already valid UTF-8, so reading its lines cannot fail.
+        Err(StreamError::Io(error)) => unreachable!("{error}"),
+    }
+}
+
+/// An elf, by its position in the input, with the total of the calories it carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elf {
+    pub index: usize,
+    pub calories: i64,
+}
+
+impl Ord for Elf {
+    /// The elves carrying more are greater, and the first one is greater among equal totals.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.calories
+            .cmp(&other.calories)
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Elf {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The `k` elves carrying the most, kept in a heap of at most `k` elves.
+struct TopK {
+    k: usize,
+    heap: BinaryHeap<Reverse<Elf>>,
+}
+
+impl TopK {
+    fn new(k: usize) -> Self {
+        TopK {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    fn push(&mut self, elf: Elf) {
+        self.heap.push(Reverse(elf));
+        if self.heap.len() > self.k {
+            self.heap.pop();
         }
     }
-    cmp::max(max, current)
+
+    /// Returns the elves from the one carrying the most.
+    fn into_sorted(self) -> Vec<Elf> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(elf)| elf)
+            .collect()
+    }
+}
+
+fn top_elves(elves: &[i64], k: usize) -> Vec<Elf> {
+    let mut top = TopK::new(k);
+    for (index, &calories) in elves.iter().enumerate() {
+        top.push(Elf { index, calories });
+    }
+    top.into_sorted()
+}
+
+fn total(elves: &[Elf]) -> i64 {
+    elves.iter().map(|elf| elf.calories).sum()
+}
+
+pub fn solve1(elves: &[i64]) -> i64 {
+    total(&top_elves(elves, 1))
 }
 
-fn part2(input: &str) -> i32 {
-    let mut vector = vec![0];
-    for line in input.lines() {
-        if !line.is_empty() {
-            *vector.last_mut().unwrap() += i32::from_str(line).unwrap_or(0);
-        } else {
-            vector.push(0)
+pub fn solve2(elves: &[i64]) -> i64 {
+    total(&top_elves(elves, 3))
+}
+
+/// Calls `f` with every elf and the total of its calories, reading the input one line at a
+/// time. Both the parsing and the streaming solutions read the elves with it.
+fn for_each_elf(reader: impl BufRead, mut f: impl FnMut(Elf)) -> Result<(), StreamError> {
+    let mut elf = None;
+    let mut index = 0;
+    for_each_line(reader, |number, line| {
+        if line.is_empty() {
+            if let Some(elf) = elf.take() {
+                f(elf);
+            }
+            return Ok(());
         }
+        let value =
+            parse_field::<i32>(Some((1, line))).map_err(|error| error.in_line(number, line))?;
+        elf.get_or_insert_with(|| {
+            index += 1;
+            Elf {
+                index: index - 1,
+                calories: 0,
+            }
+        })
+        .calories += i64::from(value);
+        Ok(())
+    })?;
+    if let Some(elf) = elf {
+        f(elf);
+    }
+    Ok(())
+}
+
+/// Returns the `k` elves carrying the most (or all of them if there are fewer), from the one
+/// carrying the most, reading the input one line at a time.
+pub fn top_k(reader: impl BufRead, k: usize) -> Result<Vec<Elf>, StreamError> {
+    let mut top = TopK::new(k);
+    for_each_elf(reader, |elf| top.push(elf))?;
+    Ok(top.into_sorted())
+}
+
+/// Returns the answers of both parts, reading the input one line at a time.
+pub fn stream(reader: impl BufRead) -> Result<(i64, i64), StreamError> {
+    let top = top_k(reader, 3)?;
+    Ok((total(&top[..top.len().min(1)]), total(&top)))
+}
+
+/// The elves carrying from `start` to `end` (excluded) calories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bucket {
+    pub start: i64,
+    pub end: i64,
+    pub count: usize,
+}
+
+/// Statistics about the calories carried by the elves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    /// Buckets of the same width from the lowest total to the highest.
+    pub histogram: Vec<Bucket>,
+}
+
+/// Returns the statistics of the totals, with a histogram of at most `buckets` buckets, or
+/// `None` without elves.
+pub fn summary(elves: &[i64], buckets: usize) -> Option<Summary> {
+    let mut sorted = elves.to_vec();
+    sorted.sort();
+    let (&lowest, &highest) = (sorted.first()?, sorted.last()?);
+
+    let count = sorted.len();
+    let mean = sorted.iter().map(|&calories| calories as f64).sum::<f64>() / count as f64;
+    let median = match count % 2 {
+        0 => (sorted[count / 2 - 1] + sorted[count / 2]) as f64 / 2.0,
+        _ => sorted[count / 2] as f64,
+    };
+
+    let width = (highest - lowest) / buckets.max(1) as i64 + 1;
+    let mut histogram: Vec<Bucket> = (0..=(highest - lowest) / width)
+        .map(|i| Bucket {
+            start: lowest + i * width,
+            end: lowest + (i + 1) * width,
+            count: 0,
+        })
+        .collect();
+    for calories in sorted {
+        histogram[((calories - lowest) / width) as usize].count += 1;
     }
-    vector.sort_by(|a, b| b.cmp(a));
-    vector[0] + vector[1] + vector[2]
+
+    Some(Summary {
+        count,
+        mean,
+        median,
+        histogram,
+    })
+}
+
+pub fn part1(input: &str) -> Result<i64, ParseError> {
+    Ok(solve1(&parse(input)?))
+}
+
+pub fn part2(input: &str) -> Result<i64, ParseError> {
+    Ok(solve2(&parse(input)?))
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 24000);
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 71023);
+
+    let error = part1("1000\n\n2000\n3k0\n").unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (4, 1, 3));
+}
+
+#[test]
+fn test_top_k() {
+    let result = top_k(get_input(DAY, true).as_bytes(), 3).unwrap();
+    let elves: Vec<(usize, i64)> = result.iter().map(|elf| (elf.index, elf.calories)).collect();
+    assert_eq!(elves, [(3, 24000), (2, 11000), (4, 10000)]);
+
+    // Fewer elves than asked for, and equal totals in the order of the input.
+    let result = top_k("1\n\n\n2\n\n1".as_bytes(), 5).unwrap();
+    let elves: Vec<(usize, i64)> = result.iter().map(|elf| (elf.index, elf.calories)).collect();
+    assert_eq!(elves, [(1, 2), (0, 1), (2, 1)]);
+    assert!(top_k("1\n2".as_bytes(), 0).unwrap().is_empty());
+    assert_eq!(solve2(&[5]), 5);
+
+    let Err(StreamError::Parse(error)) = top_k("1\n\n2\nx".as_bytes(), 1) else {
+        panic!("Expected a parse error.");
+    };
+    assert_eq!((error.line, error.column), (4, 1));
+}
+
+#[test]
+fn test_summary() {
+    let result = summary(&parse(&get_input(DAY, true)).unwrap(), 4).unwrap();
+    assert_eq!(
+        (result.count, result.mean, result.median),
+        (5, 11000.0, 10000.0)
+    );
+    let counts: Vec<(i64, i64, usize)> = result
+        .histogram
+        .iter()
+        .map(|bucket| (bucket.start, bucket.end, bucket.count))
+        .collect();
+    assert_eq!(
+        counts,
+        [
+            (4000, 9001, 2),
+            (9001, 14002, 2),
+            (14002, 19003, 0),
+            (19003, 24004, 1)
+        ]
+    );
+
+    let result = summary(&[3, 1, 2, 10], 1).unwrap();
+    assert_eq!(result.median, 2.5);
+    assert_eq!(
+        result.histogram,
+        [Bucket {
+            start: 1,
+            end: 11,
+            count: 4
+        }]
+    );
+    assert_eq!(summary(&[], 4), None);
+}
+
+#[test]
+fn test_stream() {
+    let result = stream(get_input(DAY, true).as_bytes()).unwrap();
+    assert_eq!(result, (24000, 45000));
+
+    let result = stream(get_input(DAY, false).as_bytes()).unwrap();
+    assert_eq!(result, (71023, 206289));
+
+    let Err(StreamError::Parse(error)) = stream("1000\n\n2000\n3k0\n".as_bytes()) else {
+        panic!("Expected a parse error.");
+    };
+    assert_eq!((error.line, error.column, error.length), (4, 1, 3));
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 45000);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 206289);
 }
+
+/// Generates the calories of every elf and the input listing them.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<Vec<i32>>, String)> {
+    prop::collection::vec(prop::collection::vec(0..100000, 1..5), 0..20).prop_map(|elves| {
+        let blocks: Vec<String> = elves
+            .iter()
+            .map(|calories| calories.iter().map(|c| format!("{c}\n")).collect())
+            .collect();
+        let input = blocks.join("\n");
+        (elves, input)
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_top_elves((elves, input) in input_strategy()) {
+        let totals_by_index: Vec<i64> = elves
+            .iter()
+            .map(|calories| calories.iter().map(|&c| i64::from(c)).sum())
+            .collect();
+        let mut totals = totals_by_index.clone();
+        totals.sort_by(|a, b| b.cmp(a));
+        let first = part1(&input).unwrap();
+        let top_three = part2(&input).unwrap();
+
+        prop_assert_eq!(first, totals.first().copied().unwrap_or(0));
+        prop_assert_eq!(top_three, totals.iter().take(3).sum::<i64>());
+        prop_assert!(first <= top_three && top_three <= 3 * first);
+        prop_assert_eq!(stream(input.as_bytes()).unwrap(), (first, top_three));
+
+        // The elves carrying the most are the first ones with the highest totals.
+        let top = top_k(input.as_bytes(), 3).unwrap();
+        for elf in &top {
+            prop_assert_eq!(totals_by_index[elf.index], elf.calories);
+            let better = totals_by_index
+                .iter()
+                .enumerate()
+                .filter(|&(i, &calories)| {
+                    calories > elf.calories || (calories == elf.calories && i < elf.index)
+                })
+                .count();
+            prop_assert!(better < 3);
+        }
+    }
+}
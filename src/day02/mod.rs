@@ -1,78 +1,668 @@
 // https://adventofcode.com/2022/day/2
 
 use crate::get_input;
+use crate::parse::{parse_lines_with, scan, ParseError};
+#[cfg(test)]
+use proptest::prelude::*;
+use std::cmp::{Ordering, Reverse};
+use std::error::Error;
 
 const DAY: u8 = 2;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-fn process(input: &str, map: [[i32; 3]; 3], fun: &dyn Fn(&str, [[i32; 3]; 3]) -> i32) -> i32 {
-    let mut score = 0;
-    for line in input.lines() {
-        let result = fun(line, map);
-        score += result;
+/// A round as the indices of its letters in the two columns of the game.
+pub type Round = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+/// What the letters of the second column stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// The move to play.
+    Move,
+    /// The outcome to reach, from a loss to a win.
+    Outcome,
+}
+
+/// A game of an odd number of moves in a cycle, where each move beats the half of the other
+/// moves just before it (e.g. rock, paper, scissors, or rock, Spock, paper, lizard, scissors).
+#[derive(Debug, Clone)]
+pub struct Game<'a> {
+    /// The score of every move, in the order of the cycle.
+    pub move_scores: &'a [i32],
+    /// The score of a loss, a draw and a win.
+    pub outcome_scores: [i32; 3],
+    /// The letters of the moves of the opponent.
+    pub opponent_letters: &'a [char],
+    /// The letters of the second column, standing for the moves or for the outcomes.
+    pub second_letters: &'a [char],
+    pub second_column: Column,
+}
+
+/// The game of the first part, where the second column is the move to play.
+pub const GAME1: Game = Game {
+    move_scores: &[1, 2, 3],
+    outcome_scores: [0, 3, 6],
+    opponent_letters: &['A', 'B', 'C'],
+    second_letters: &['X', 'Y', 'Z'],
+    second_column: Column::Move,
+};
+
+/// The game of the second part, where the second column is the outcome to reach.
+pub const GAME2: Game = Game {
+    second_column: Column::Outcome,
+    ..GAME1
+};
+
+fn parse_letter(letter: char, letters: &[char], column: usize) -> Result<usize, ParseError> {
+    letters
+        .iter()
+        .position(|&other| other == letter)
+        .ok_or_else(|| {
+            let mut expected: Vec<String> =
+                letters.iter().map(|letter| format!("`{letter}`")).collect();
+            let last = expected.pop().unwrap_or_default();
+            let expected = match expected.is_empty() {
+                true => last,
+                false => format!("{} or {last}", expected.join(", ")),
+            };
+            ParseError::new(column, format!("expected {expected}, found `{letter}`"))
+        })
+}
+
+impl Game<'_> {
+    /// Panics if the moves cannot form a cycle where some win and some lose, or if the letters do
+    /// not match the moves.
+    fn check(&self) {
+        let moves = self.move_scores.len();
+        assert!(moves % 2 == 1, "The game has an even number of moves.");
+        assert!(moves >= 3, "The game has a single move, which cannot win.");
+        assert_eq!(self.opponent_letters.len(), moves, "A move has no letter.");
+        let letters = match self.second_column {
+            Column::Move => moves,
+            Column::Outcome => 3,
+        };
+        assert_eq!(
+            self.second_letters.len(),
+            letters,
+            "A letter has no meaning."
+        );
+    }
+
+    fn parse_round(&self, line: &str) -> Result<Round, ParseError> {
+        let (first, second) = scan!(line, "{} {}", char, char)?;
+        Ok((
+            parse_letter(first, self.opponent_letters, 1)?,
+            parse_letter(second, self.second_letters, 3)?,
+        ))
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Vec<Round>, ParseError> {
+        self.check();
+        parse_lines_with(input, |line| self.parse_round(line))
+    }
+
+    pub fn outcome(&self, opponent: usize, you: usize) -> Outcome {
+        let moves = self.move_scores.len();
+        match (you + moves - opponent) % moves {
+            0 => Outcome::Draw,
+            distance if distance <= moves / 2 => Outcome::Win,
+            _ => Outcome::Loss,
+        }
+    }
+
+    /// Returns the move to play in the round. When several moves reach the outcome, the one
+    /// scoring the most is played.
+    pub fn play(&self, (opponent, second): Round) -> usize {
+        match self.second_column {
+            Column::Move => second,
+            Column::Outcome => (0..self.move_scores.len())
+                .filter(|&you| self.outcome(opponent, you) as usize == second)
+                .max_by_key(|&you| self.move_scores[you])
+                .unwrap(),
+        }
+    }
+
+    /// Returns your score when you play `you` against `opponent`.
+    pub fn move_score(&self, you: usize, opponent: usize) -> i32 {
+        self.move_scores[you] + self.outcome_scores[self.outcome(opponent, you) as usize]
+    }
+
+    pub fn round_score(&self, round: Round) -> i32 {
+        self.move_score(self.play(round), round.0)
+    }
+
+    pub fn score(&self, rounds: &[Round]) -> i32 {
+        self.check();
+        rounds.iter().map(|&round| self.round_score(round)).sum()
+    }
+}
+
+/// Mixed strategies of both players, as the probabilities of their moves, where neither can
+/// change its strategy alone to do better. The opponent tries to lower your score, which is
+/// `value` on average.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equilibrium {
+    pub you: Vec<f64>,
+    pub opponent: Vec<f64>,
+    pub value: f64,
+}
+
+/// The tolerance of the comparisons of probabilities and expected scores.
+const EPSILON: f64 = 1e-9;
+
+/// Solves the linear equations given as the rows of an augmented matrix, by Gaussian elimination.
+/// Returns `None` if they do not have a single solution.
+fn solve_linear(mut rows: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let size = rows.len();
+    for column in 0..size {
+        let pivot = (column..size)
+            .max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+        if rows[pivot][column].abs() < EPSILON {
+            return None;
+        }
+        rows.swap(column, pivot);
+        for row in 0..size {
+            if row != column {
+                let factor = rows[row][column] / rows[column][column];
+                let pivot_row = rows[column].clone();
+                for (value, pivot_value) in rows[row].iter_mut().zip(pivot_row) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+    Some((0..size).map(|i| rows[i][size] / rows[i][i]).collect())
+}
+
+/// Returns the moves of a subset given as a bit mask.
+fn moves_in(mask: usize, moves: usize) -> Vec<usize> {
+    (0..moves).filter(|i| mask >> i & 1 == 1).collect()
+}
+
+impl Game<'_> {
+    /// Returns how often the opponent plays every move in the guide (the same for every move
+    /// without rounds).
+    pub fn opponent_distribution(&self, rounds: &[Round]) -> Vec<f64> {
+        let moves = self.move_scores.len();
+        if rounds.is_empty() {
+            return vec![1.0 / moves as f64; moves];
+        }
+        let mut distribution = vec![0.0; moves];
+        for &(opponent, _) in rounds {
+            distribution[opponent] += 1.0 / rounds.len() as f64;
+        }
+        distribution
+    }
+
+    /// Returns your expected score for every move, against a distribution of the moves of the
+    /// opponent.
+    pub fn expected_scores(&self, distribution: &[f64]) -> Vec<f64> {
+        (0..self.move_scores.len())
+            .map(|you| {
+                distribution
+                    .iter()
+                    .enumerate()
+                    .map(|(opponent, p)| p * self.move_score(you, opponent) as f64)
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Returns the move with the best expected score against a distribution of the moves of the
+    /// opponent (the first one among equal scores), with its expected score.
+    pub fn best_response(&self, distribution: &[f64]) -> (usize, f64) {
+        let mut best = (0, f64::MIN);
+        for (you, score) in self.expected_scores(distribution).into_iter().enumerate() {
+            if score > best.1 + EPSILON {
+                best = (you, score);
+            }
+        }
+        best
+    }
+
+    /// Returns the equilibrium of the game where the opponent tries to lower your score.
+    ///
+    /// The supports of the strategies (the moves played) are enumerated, from the smallest, and
+    /// both players are made indifferent between the moves of the other's support. This finds
+    /// an equilibrium for every game whose supports have the same size, e.g. when no two moves
+    /// score the same against every move.
+    pub fn equilibrium(&self) -> Option<Equilibrium> {
+        self.check();
+        let moves = self.move_scores.len();
+        let score = |you: usize, opponent: usize| self.move_score(you, opponent) as f64;
+
+        for size in 1..=moves {
+            let supports: Vec<Vec<usize>> = (1..1 << moves)
+                .filter(|mask: &usize| mask.count_ones() as usize == size)
+                .map(|mask| moves_in(mask, moves))
+                .collect();
+            for yours in &supports {
+                for theirs in &supports {
+                    // Your probabilities and the value, which is the same for every move of the
+                    // opponent, and the other way around.
+                    let mut rows: Vec<Vec<f64>> = theirs
+                        .iter()
+                        .map(|&opponent| {
+                            let mut row: Vec<f64> =
+                                yours.iter().map(|&you| score(you, opponent)).collect();
+                            row.extend([-1.0, 0.0]);
+                            row
+                        })
+                        .collect();
+                    rows.push([vec![1.0; size], vec![0.0, 1.0]].concat());
+                    let Some(you) = solve_linear(rows) else {
+                        continue;
+                    };
+                    let mut rows: Vec<Vec<f64>> = yours
+                        .iter()
+                        .map(|&you| {
+                            let mut row: Vec<f64> = theirs
+                                .iter()
+                                .map(|&opponent| score(you, opponent))
+                                .collect();
+                            row.extend([-1.0, 0.0]);
+                            row
+                        })
+                        .collect();
+                    rows.push([vec![1.0; size], vec![0.0, 1.0]].concat());
+                    let Some(opponent) = solve_linear(rows) else {
+                        continue;
+                    };
+
+                    let mut equilibrium = Equilibrium {
+                        you: vec![0.0; moves],
+                        opponent: vec![0.0; moves],
+                        value: you[size],
+                    };
+                    for (i, &move_) in yours.iter().enumerate() {
+                        equilibrium.you[move_] = you[i];
+                    }
+                    for (i, &move_) in theirs.iter().enumerate() {
+                        equilibrium.opponent[move_] = opponent[i];
+                    }
+                    if self.is_equilibrium(&equilibrium) {
+                        return Some(equilibrium);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns whether no move does better for either player against the other's strategy.
+    fn is_equilibrium(&self, equilibrium: &Equilibrium) -> bool {
+        let Equilibrium {
+            you,
+            opponent,
+            value,
+        } = equilibrium;
+        if you.iter().chain(opponent).any(|&p| p < -EPSILON) {
+            return false;
+        }
+
+        // Your best move against the opponent scores the value, and so does their best one.
+        let yours = self.expected_scores(opponent);
+        let theirs = (0..you.len()).map(|opponent| {
+            you.iter()
+                .enumerate()
+                .map(|(move_, p)| p * self.move_score(move_, opponent) as f64)
+                .sum::<f64>()
+        });
+        yours.iter().all(|&score| score <= value + EPSILON)
+            && theirs.into_iter().all(|score| score >= value - EPSILON)
     }
-    score
 }
 
-fn result1(line: &str, map: [[i32; 3]; 3]) -> i32 {
-    let opponent = line[0..1].chars().next().unwrap();
-    let you = line[2..3].chars().next().unwrap();
+/// A player of the tournament.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bot {
+    /// Always plays the same move.
+    Fixed(usize),
+    /// Plays every move in turn, in the order of the cycle, from the given one.
+    Cycling(usize),
+    /// Plays the best response to the moves of the opponent so far.
+    FrequencyCounting,
+}
 
-    let match_score = map[opponent as usize - 'A' as usize][you as usize - 'X' as usize];
-    let figure_score = you as i32 - 'X' as i32 + 1;
+impl Bot {
+    fn play(&self, game: &Game, round: usize, opponent_moves: &[usize]) -> usize {
+        match *self {
+            Bot::Fixed(move_) => move_,
+            Bot::Cycling(start) => (start + round) % game.move_scores.len(),
+            Bot::FrequencyCounting => {
+                let rounds: Vec<Round> = opponent_moves.iter().map(|&move_| (move_, 0)).collect();
+                game.best_response(&game.opponent_distribution(&rounds)).0
+            }
+        }
+    }
+}
 
-    match_score + figure_score
+/// The results of a bot in the tournament. A match is won by the bot scoring the most in its
+/// rounds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub name: String,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub score: i64,
 }
 
-fn part1(input: &str) -> i32 {
-    // The first dimension is the opponent's figure - 'A'.
-    // The second dimension is your figure - 'X';
-    // The third dimension is the score of the match.
-    let map = [[3, 6, 0], [0, 3, 6], [6, 0, 3]];
+impl Game<'_> {
+    /// Plays a match of `rounds` rounds between every two bots, and returns the standings from
+    /// the best bot: with the most wins, then the most draws, then the highest score. Returns an
+    /// error if a bot plays a move which is not in the game.
+    pub fn tournament(&self, bots: &[(&str, Bot)], rounds: usize) -> Result<Vec<Standing>, String> {
+        self.check();
+        let moves = self.move_scores.len();
+        for (name, bot) in bots {
+            if let Bot::Fixed(move_) | Bot::Cycling(move_) = *bot {
+                if move_ >= moves {
+                    return Err(format!(
+                        "expected a move from 0 to {} for the bot {name}, found {move_}",
+                        moves - 1
+                    ));
+                }
+            }
+        }
+        let mut standings: Vec<Standing> = bots
+            .iter()
+            .map(|(name, _)| Standing {
+                name: name.to_string(),
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                score: 0,
+            })
+            .collect();
+
+        for first in 0..bots.len() {
+            for second in first + 1..bots.len() {
+                let mut moves: [Vec<usize>; 2] = [vec![], vec![]];
+                let mut scores = [0_i64; 2];
+                for round in 0..rounds {
+                    let played = [
+                        bots[first].1.play(self, round, &moves[1]),
+                        bots[second].1.play(self, round, &moves[0]),
+                    ];
+                    scores[0] += i64::from(self.move_score(played[0], played[1]));
+                    scores[1] += i64::from(self.move_score(played[1], played[0]));
+                    moves[0].push(played[0]);
+                    moves[1].push(played[1]);
+                }
 
-    process(input, map, &result1)
+                for (bot, other) in [(first, 1), (second, 0)] {
+                    let score = scores[1 - other];
+                    let standing = &mut standings[bot];
+                    standing.score += score;
+                    match score.cmp(&scores[other]) {
+                        Ordering::Greater => standing.wins += 1,
+                        Ordering::Equal => standing.draws += 1,
+                        Ordering::Less => standing.losses += 1,
+                    }
+                }
+            }
+        }
+
+        standings.sort_by_key(|standing| Reverse((standing.wins, standing.draws, standing.score)));
+        Ok(standings)
+    }
 }
 
-fn result2(line: &str, map: [[i32; 3]; 3]) -> i32 {
-    let opponent = line[0..1].chars().next().unwrap();
-    let expected_result = line[2..3].chars().next().unwrap();
+/// Formats the standings as a table, one bot per line.
+pub fn standings_table(standings: &[Standing]) -> String {
+    let width = standings
+        .iter()
+        .map(|standing| standing.name.len())
+        .chain(["bot".len()])
+        .max()
+        .unwrap();
+    let mut lines = vec![format!(
+        "{:<width$} {:>5} {:>5} {:>6} {:>8}",
+        "bot", "wins", "draws", "losses", "score"
+    )];
+    for standing in standings {
+        lines.push(format!(
+            "{:<width$} {:>5} {:>5} {:>6} {:>8}",
+            standing.name, standing.wins, standing.draws, standing.losses, standing.score
+        ));
+    }
+    lines.join("\n")
+}
 
-    let figure_score =
-        map[opponent as usize - 'A' as usize][expected_result as usize - 'X' as usize];
-    let match_score = (expected_result as i32 - 'X' as i32) * 3;
+/// Parses the strategy guide, whose letters are the same in both parts.
+pub fn parse(input: &str) -> Result<Vec<Round>, ParseError> {
+    GAME1.parse(input)
+}
 
-    figure_score + match_score
+pub fn solve1(rounds: &[Round]) -> i32 {
+    GAME1.score(rounds)
 }
 
-fn part2(input: &str) -> i32 {
-    // The first dimension is the opponent's figure - 'A'.
-    // The second dimension is the expected result (0 - lost, 1 - draw, 2 - won).
-    // The values are scores of the figures you should use.
-    let map = [[3, 1, 2], [1, 2, 3], [2, 3, 1]];
+pub fn part1(input: &str) -> Result<i32, ParseError> {
+    Ok(solve1(&parse(input)?))
+}
 
-    process(input, map, &result2)
+pub fn solve2(rounds: &[Round]) -> i32 {
+    GAME2.score(rounds)
+}
+
+pub fn part2(input: &str) -> Result<i32, ParseError> {
+    Ok(solve2(&parse(input)?))
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 15);
 
-    let result2 = part1(&get_input(DAY, false));
+    let result2 = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result2, 13675);
+
+    let error = part1("A Y\nB W").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 3));
+    assert_eq!(error.message, "expected `X`, `Y` or `Z`, found `W`");
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 12);
 
-    let result2 = part2(&get_input(DAY, false));
+    let result2 = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result2, 14184);
+
+    let error = part2("A Y\nBY").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 1));
+}
+
+#[test]
+fn test_game() {
+    // Rock, Spock, paper, lizard, scissors.
+    let game = Game {
+        move_scores: &[1, 2, 3, 4, 5],
+        outcome_scores: [0, 3, 6],
+        opponent_letters: &['R', 'S', 'P', 'L', 'C'],
+        second_letters: &['r', 's', 'p', 'l', 'c'],
+        second_column: Column::Move,
+    };
+    assert_eq!(game.outcome(0, 4), Outcome::Loss);
+    assert_eq!(game.outcome(3, 1), Outcome::Loss);
+    assert_eq!(game.outcome(1, 3), Outcome::Win);
+    assert_eq!(game.outcome(2, 2), Outcome::Draw);
+
+    let rounds = game.parse("R p\nL c\nS S").unwrap_err();
+    assert_eq!(
+        rounds.message,
+        "expected `r`, `s`, `p`, `l` or `c`, found `S`"
+    );
+    let rounds = game.parse("R p\nL c\nS s").unwrap();
+    assert_eq!(game.score(&rounds), (3 + 6) + (5 + 6) + (2 + 3));
+
+    // Against Spock, both lizard and paper win, and lizard scores more. Against scissors, lizard
+    // and paper lose.
+    let game = Game {
+        second_letters: &['l', 'd', 'w'],
+        second_column: Column::Outcome,
+        ..game
+    };
+    let rounds = game.parse("S w\nC d\nC l").unwrap();
+    assert_eq!(game.play(rounds[0]), 3);
+    assert_eq!(game.score(&rounds), (4 + 6) + (5 + 3) + 4);
+}
+
+#[test]
+#[should_panic(expected = "The game has a single move, which cannot win.")]
+fn test_single_move() {
+    let game = Game {
+        move_scores: &[1],
+        opponent_letters: &['A'],
+        ..GAME2
+    };
+    game.parse("A Z").unwrap();
+}
+
+#[test]
+fn test_analysis() {
+    let rounds = parse(&get_input(DAY, true)).unwrap();
+    let distribution = GAME1.opponent_distribution(&rounds);
+    assert_eq!(distribution, [1.0 / 3.0; 3]);
+    // Against every move as often, scissors scores (3 + 9 + 6) / 3 on average.
+    let (best, score) = GAME1.best_response(&distribution);
+    assert_eq!(best, 2);
+    assert!((score - 6.0).abs() < EPSILON);
+
+    let rounds = parse(&get_input(DAY, false)).unwrap();
+    let distribution = GAME1.opponent_distribution(&rounds);
+    assert!((distribution.iter().sum::<f64>() - 1.0).abs() < EPSILON);
+    let (best, score) = GAME1.best_response(&distribution);
+    let scores = GAME1.expected_scores(&distribution);
+    assert!(scores.iter().all(|&other| other <= score));
+    assert_eq!(scores[best], score);
+
+    // Without the scores of the moves, the equilibrium plays every move as often.
+    let game = Game {
+        move_scores: &[0, 0, 0, 0, 0],
+        opponent_letters: &['A', 'B', 'C', 'D', 'E'],
+        second_letters: &['V', 'W', 'X', 'Y', 'Z'],
+        ..GAME1
+    };
+    let equilibrium = game.equilibrium().unwrap();
+    for p in equilibrium.you.iter().chain(&equilibrium.opponent) {
+        assert!((p - 0.2).abs() < EPSILON);
+    }
+    assert!((equilibrium.value - 3.0).abs() < EPSILON);
+
+    // The opponent plays paper less often, as it loses to scissors, which scores the most.
+    let equilibrium = GAME1.equilibrium().unwrap();
+    let expected = [
+        ([1.0 / 3.0; 3], equilibrium.you),
+        ([4.0 / 9.0, 1.0 / 9.0, 4.0 / 9.0], equilibrium.opponent),
+    ];
+    for (expected, result) in expected {
+        assert!(expected
+            .iter()
+            .zip(result)
+            .all(|(p, q)| (p - q).abs() < EPSILON));
+    }
+    assert!((equilibrium.value - 5.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_tournament() {
+    let bots = [
+        ("rock", Bot::Fixed(0)),
+        ("cycling", Bot::Cycling(0)),
+        ("frequency", Bot::FrequencyCounting),
+    ];
+    let standings = GAME1.tournament(&bots, 30).unwrap();
+    let results: Vec<(&str, usize, usize, usize)> = standings
+        .iter()
+        .map(|standing| {
+            let Standing {
+                name,
+                wins,
+                draws,
+                losses,
+                ..
+            } = standing;
+            (name.as_str(), *wins, *draws, *losses)
+        })
+        .collect();
+    assert_eq!(
+        results,
+        [
+            ("frequency", 2, 0, 0),
+            ("cycling", 1, 0, 1),
+            ("rock", 0, 0, 2)
+        ]
+    );
+    // Rock beats the first guess, then scores 1 against paper in every round.
+    assert_eq!(
+        GAME1.tournament(&[bots[0], bots[2]], 3).unwrap()[1].score,
+        7 + 1 + 1
+    );
+    assert_eq!(
+        GAME1.tournament(&[bots[0], ("lizard", Bot::Fixed(3))], 3),
+        Err("expected a move from 0 to 2 for the bot lizard, found 3".to_string())
+    );
+
+    let table = standings_table(&standings);
+    assert_eq!(table.lines().count(), 4);
+    assert!(
+        table.starts_with("bot        wins draws losses    score\nfrequency     2     0      0")
+    );
+}
+
+#[cfg(test)]
+fn round_line((first, second): Round) -> String {
+    format!(
+        "{} {}",
+        (b'A' + first as u8) as char,
+        (b'X' + second as u8) as char
+    )
+}
+
+/// Generates the rounds of a strategy guide and the guide itself.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<Round>, String)> {
+    prop::collection::vec((0..3_usize, 0..3_usize), 0..50).prop_map(|rounds| {
+        let lines: Vec<String> = rounds.iter().map(|&round| round_line(round)).collect();
+        (rounds, lines.join("\n"))
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_scores_add_up((rounds, input) in input_strategy()) {
+        for part in [part1, part2] {
+            let total: i32 = rounds.iter().map(|&round| part(&round_line(round)).unwrap()).sum();
+            prop_assert_eq!(part(&input).unwrap(), total);
+        }
+    }
+
+    #[test]
+    fn property_outcome_is_reached(opponent in 0..3_usize, outcome in 0..3_usize) {
+        // The second part picks the figure with the expected outcome, which scores the same in
+        // the first part.
+        let score = part2(&round_line((opponent, outcome))).unwrap();
+        let figure = (score - 3 * outcome as i32 - 1) as usize;
+        prop_assert!(figure < 3);
+        prop_assert_eq!(part1(&round_line((opponent, figure))).unwrap(), score);
+        prop_assert_eq!((figure + 4 - opponent) % 3, outcome);
+    }
 }
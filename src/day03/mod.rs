@@ -1,89 +1,355 @@
 // https://adventofcode.com/2022/day/3
 
+use crate::bit_set::BitSet64;
 use crate::get_input;
-use std::collections::HashSet;
+use crate::parse::{parse_lines_with, ParseError};
+#[cfg(test)]
+use proptest::prelude::*;
+use std::error::Error;
 
 const DAY: u8 = 3;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-fn get_priority(item_type: char) -> i32 {
-    if item_type <= 'Z' {
-        return item_type as i32 - 'A' as i32 + 27;
+/// The items that can be in the rucksacks (at most 64), with their priorities, and the number
+/// of rucksacks in a group.
+#[derive(Debug, Clone)]
+pub struct Rules<'a> {
+    pub items: &'a [char],
+    /// The priority of every item, in the same order.
+    pub priorities: &'a [i32],
+    pub group_size: usize,
+}
+
+const fn letters() -> [char; 52] {
+    let mut items = ['a'; 52];
+    let mut i = 0;
+    while i < 26 {
+        items[i] = (b'a' + i as u8) as char;
+        items[26 + i] = (b'A' + i as u8) as char;
+        i += 1;
     }
+    items
+}
 
-    item_type as i32 - 'a' as i32 + 1
+const fn letter_priorities() -> [i32; 52] {
+    let mut priorities = [0; 52];
+    let mut i = 0;
+    while i < 52 {
+        priorities[i] = i as i32 + 1;
+        i += 1;
+    }
+    priorities
 }
 
-fn result1(line: &str) -> i32 {
-    let size = line.len() / 2;
-    let first = &line[..size];
-    let second = &line[size..];
+const LETTERS: [char; 52] = letters();
+const LETTER_PRIORITIES: [i32; 52] = letter_priorities();
 
-    let first: HashSet<char> = first.chars().collect();
-    let second: HashSet<char> = second.chars().collect();
+/// The rules of the puzzle: the letters from `a` to `z` and from `A` to `Z`, of priorities 1 to
+/// 52, in groups of three rucksacks.
+pub const RULES: Rules = Rules {
+    items: &LETTERS,
+    priorities: &LETTER_PRIORITIES,
+    group_size: 3,
+};
 
-    let common_char = first.intersection(&second).last().copied().unwrap();
+/// An item found in all the rucksacks (or compartments) compared, with its priority.
+pub type SharedItem = (char, i32);
 
-    get_priority(common_char)
+/// Returns the priority of the only shared item, or the problem as the error.
+fn single_item(shared: &[SharedItem], place: &str) -> Result<i32, String> {
+    match shared {
+        [] => Err(format!("expected an item {place}")),
+        [(_, priority)] => Ok(*priority),
+        _ => {
+            let items: Vec<String> = shared.iter().map(|(item, _)| format!("`{item}`")).collect();
+            Err(format!(
+                "expected a single item {place}, found {}",
+                items.join(", ")
+            ))
+        }
+    }
 }
 
-fn part1(input: &str) -> i32 {
-    let mut score = 0;
-    for line in input.lines() {
-        let result = result1(line);
-        score += result;
+impl Rules<'_> {
+    /// Panics if the items do not fit in a `BitSet64` or do not all have a priority.
+    fn check(&self) {
+        assert!(
+            self.items.len() <= BitSet64::CAPACITY,
+            "There are more than {} items.",
+            BitSet64::CAPACITY
+        );
+        assert_eq!(
+            self.items.len(),
+            self.priorities.len(),
+            "An item has no priority."
+        );
+        assert!(self.group_size > 0, "The groups are empty.");
+    }
+
+    fn index(&self, item: char) -> Option<usize> {
+        self.items.iter().position(|&other| other == item)
     }
-    score
-}
 
-fn result2(first: &str, second: &str, third: &str) -> i32 {
-    // println!("{}, {}, {}", first, second, third);
+    /// Checks that the rucksack only contains items and can be split in two compartments.
+    fn parse_rucksack<'a>(&self, line: &'a str) -> Result<&'a str, ParseError> {
+        if let Some((i, item)) = line.char_indices().find(|&(_, c)| self.index(c).is_none()) {
+            return Err(ParseError::new(
+                i + 1,
+                format!("expected an item, found `{item}`"),
+            ));
+        }
+        if !line.chars().count().is_multiple_of(2) {
+            return Err(ParseError::new(1, "expected an even number of items")
+                .with_length(line.chars().count()));
+        }
+        Ok(line)
+    }
 
-    let first: HashSet<char> = first.chars().collect();
-    let second: HashSet<char> = second.chars().collect();
-    let third: HashSet<char> = third.chars().collect();
+    pub fn parse<'a>(&self, input: &'a str) -> Result<Vec<&'a str>, ParseError> {
+        self.check();
+        parse_lines_with(input, |line| self.parse_rucksack(line))
+    }
 
-    let intersection: HashSet<char> = first.intersection(&second).copied().collect();
-    let common_char = intersection.intersection(&third).last().copied().unwrap();
-    // println!("{}", common_char);
+    /// Returns the items in all the sets of items.
+    fn shared<'a>(&self, sets: impl IntoIterator<Item = &'a str>) -> Vec<SharedItem> {
+        let mut shared: Option<BitSet64> = None;
+        for set in sets {
+            let set: BitSet64 = set.chars().filter_map(|item| self.index(item)).collect();
+            shared = Some(shared.map_or(set, |shared| shared.intersection(&set)));
+        }
+        shared
+            .unwrap_or_default()
+            .iter()
+            .map(|i| (self.items[i], self.priorities[i]))
+            .collect()
+    }
 
-    get_priority(common_char)
-}
+    /// Returns the items in both compartments of every rucksack.
+    pub fn shared_in_rucksacks(&self, rucksacks: &[&str]) -> Vec<Vec<SharedItem>> {
+        rucksacks
+            .iter()
+            .map(|rucksack| {
+                let middle = rucksack
+                    .char_indices()
+                    .nth(rucksack.chars().count() / 2)
+                    .map_or(rucksack.len(), |(i, _)| i);
+                let (first, second) = rucksack.split_at(middle);
+                self.shared([first, second])
+            })
+            .collect()
+    }
 
-fn part2(input: &str) -> i32 {
-    let mut score = 0;
-    let mut rucksacks: [&str; 3] = ["empty"; 3];
+    /// Returns the items in all the rucksacks of every group (the last one can be incomplete).
+    pub fn shared_in_groups(&self, rucksacks: &[&str]) -> Vec<Vec<SharedItem>> {
+        rucksacks
+            .chunks(self.group_size)
+            .map(|group| self.shared(group.iter().copied()))
+            .collect()
+    }
 
-    for (i, line) in input.lines().enumerate() {
-        rucksacks[i % 3] = line;
+    /// Returns the sum of the priorities of the items in both compartments of every rucksack.
+    pub fn solve1(&self, rucksacks: &[&str]) -> Result<i32, ParseError> {
+        let mut score = 0;
+        for (i, shared) in self.shared_in_rucksacks(rucksacks).iter().enumerate() {
+            let line = rucksacks[i];
+            score += single_item(shared, "in both compartments")
+                .map_err(|message| ParseError::whole_line(i + 1, line, message))?;
+        }
+        Ok(score)
+    }
 
-        if i % 3 == 2 {
-            score += result2(rucksacks[0], rucksacks[1], rucksacks[2]);
+    /// Returns the sum of the priorities of the badges, the items in every rucksack of a group.
+    pub fn solve2(&self, rucksacks: &[&str]) -> Result<i32, ParseError> {
+        let mut score = 0;
+        for (i, shared) in self.shared_in_groups(rucksacks).iter().enumerate() {
+            let line = i * self.group_size;
+            if rucksacks.len() - line < self.group_size {
+                return Err(ParseError::whole_line(
+                    line + 1,
+                    rucksacks[line],
+                    format!("expected a group of {} rucksacks", self.group_size),
+                ));
+            }
+            score += single_item(shared, "common to the whole group")
+                .map_err(|message| ParseError::whole_line(line + 1, rucksacks[line], message))?;
         }
+        Ok(score)
     }
-    score
+}
+
+pub fn parse(input: &str) -> Result<Vec<&str>, ParseError> {
+    RULES.parse(input)
+}
+
+pub fn solve1(rucksacks: &[&str]) -> Result<i32, ParseError> {
+    RULES.solve1(rucksacks)
+}
+
+pub fn part1(input: &str) -> Result<i32, ParseError> {
+    solve1(&parse(input)?)
+}
+
+pub fn solve2(rucksacks: &[&str]) -> Result<i32, ParseError> {
+    RULES.solve2(rucksacks)
+}
+
+pub fn part2(input: &str) -> Result<i32, ParseError> {
+    solve2(&parse(input)?)
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 157);
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 7889);
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 70);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 2825);
+
+    let error = part2("ab\nac\nad\nae").unwrap_err();
+    assert_eq!(error.line, 4);
+    assert_eq!(
+        part1("abcA").unwrap_err().message,
+        "expected an item in both compartments"
+    );
+    assert_eq!(part1("ab-a").unwrap_err().column, 3);
+    assert_eq!(
+        part1("abba").unwrap_err().message,
+        "expected a single item in both compartments, found `a`, `b`"
+    );
+    assert_eq!(
+        part2("ab\nab\nab").unwrap_err().message,
+        "expected a single item common to the whole group, found `a`, `b`"
+    );
+}
+
+#[test]
+fn test_rules() {
+    let input = get_input(DAY, true);
+    let rucksacks = parse(&input).unwrap();
+    let shared = RULES.shared_in_rucksacks(&rucksacks);
+    assert_eq!(shared[0], [('p', 16)]);
+    assert_eq!(shared[1], [('L', 38)]);
+    assert_eq!(
+        RULES.shared_in_groups(&rucksacks),
+        [[('r', 18)], [('Z', 52)]]
+    );
+
+    // Digits, with odd ones worth more, in groups of two.
+    let rules = Rules {
+        items: &['0', '1', '2', '3', '4'],
+        priorities: &[1, 10, 2, 30, 4],
+        group_size: 2,
+    };
+    let rucksacks = rules.parse("0112\n14\n2442").unwrap();
+    assert_eq!(
+        rules.shared_in_rucksacks(&rucksacks),
+        [vec![('1', 10)], vec![], vec![('2', 2), ('4', 4)]]
+    );
+    assert_eq!(
+        rules.shared_in_groups(&rucksacks),
+        [vec![('1', 10)], vec![('2', 2), ('4', 4)]]
+    );
+    assert_eq!(rules.solve1(&rucksacks[..1]), Ok(10));
+    assert_eq!(rules.solve1(&rucksacks).unwrap_err().line, 2);
+    assert_eq!(rules.solve2(&rucksacks[..2]), Ok(10));
+    assert_eq!(
+        rules.solve2(&rucksacks).unwrap_err().message,
+        "expected a group of 2 rucksacks"
+    );
+    assert_eq!(
+        rules.parse("015").unwrap_err().message,
+        "expected an item, found `5`"
+    );
+}
+
+/// Generates a group of three rucksacks. Each of them has one item in both compartments and the
+/// badge in its first compartment, and its other items are only in this compartment of this
+/// rucksack.
+#[cfg(test)]
+fn group_strategy() -> impl Strategy<Value = Vec<String>> {
+    let picks = || prop::collection::vec((0..8_usize, 0..8_usize), 0..10);
+    (
+        Just(LETTERS.to_vec()).prop_shuffle(),
+        [picks(), picks(), picks()],
+        [0..8_usize, 0..8_usize, 0..8_usize],
+    )
+        .prop_flat_map(|(letters, picks, extras)| {
+            let badge = letters[0];
+            let rucksacks: Vec<_> = (0..3)
+                .map(|k| {
+                    let pool = |compartment: usize| &letters[4 + 8 * (2 * k + compartment)..][..8];
+                    let (mut first, mut second): (Vec<char>, Vec<char>) = picks[k]
+                        .iter()
+                        .map(|&(a, b)| (pool(0)[a], pool(1)[b]))
+                        .unzip();
+                    first.extend([letters[1 + k], badge]);
+                    second.extend([letters[1 + k], pool(1)[extras[k]]]);
+                    (Just(first).prop_shuffle(), Just(second).prop_shuffle()).prop_map(
+                        |(first, second)| first.into_iter().chain(second).collect::<String>(),
+                    )
+                })
+                .collect();
+            rucksacks
+        })
+}
+
+/// Generates groups of three rucksacks and the input listing them.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<String>, String)> {
+    prop::collection::vec(group_strategy(), 0..10).prop_map(|groups| {
+        let rucksacks: Vec<String> = groups.into_iter().flatten().collect();
+        let input = rucksacks.join("\n");
+        (rucksacks, input)
+    })
+}
+
+/// Returns the priorities of the items in every set, by brute force.
+#[cfg(test)]
+fn common_priorities(sets: &[&str]) -> Vec<i32> {
+    (1..=52)
+        .filter(|&priority| {
+            let item = LETTERS[priority as usize - 1];
+            sets.iter().all(|set| set.contains(item))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_priorities((rucksacks, input) in input_strategy()) {
+        let mut total = 0;
+        for rucksack in &rucksacks {
+            let (first, second) = rucksack.split_at(rucksack.len() / 2);
+            let priorities = common_priorities(&[first, second]);
+            prop_assert_eq!(priorities.len(), 1);
+            total += priorities[0];
+        }
+        prop_assert_eq!(part1(&input).unwrap(), total);
+
+        let mut total = 0;
+        for group in rucksacks.chunks(3) {
+            let group: Vec<&str> = group.iter().map(String::as_str).collect();
+            let priorities = common_priorities(&group);
+            prop_assert_eq!(priorities.len(), 1);
+            total += priorities[0];
+        }
+        prop_assert_eq!(part2(&input).unwrap(), total);
+    }
 }
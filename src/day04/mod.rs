@@ -1,92 +1,516 @@
 // https://adventofcode.com/2022/day/4
 
 use crate::get_input;
-use std::str::FromStr;
-// use std::collections::HashSet;
+use crate::parse::{parse_lines_with, scan, ParseError};
+#[cfg(test)]
+use proptest::prelude::*;
+use std::cmp::Ordering;
+use std::error::Error;
 
 const DAY: u8 = 4;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-fn process(input: &str, function: &dyn Fn(&str) -> i32) -> i32 {
-    let mut score = 0;
-    for line in input.lines() {
-        let result = function(line);
-        score += result;
+pub type Assignments = (i32, i32, i32, i32);
+
+fn parse_assignments(line: &str) -> Result<Assignments, ParseError> {
+    let assignments = scan!(line, "{}-{},{}-{}", i32, i32, i32, i32)?;
+    let (first_lower, first_upper, second_lower, second_upper) = assignments;
+
+    // The scan found the comma, so the wrong assignment can be underlined.
+    let comma = line.find(',').unwrap();
+    for (lower, upper, start, end) in [
+        (first_lower, first_upper, 0, comma),
+        (second_lower, second_upper, comma + 1, line.len()),
+    ] {
+        if lower > upper {
+            return Err(ParseError::new(
+                start + 1,
+                "expected the first section to be at most the last one",
+            )
+            .with_length(end - start));
+        }
     }
-    score
+    Ok(assignments)
 }
 
-fn result1(line: &str) -> i32 {
-    let split: Vec<&str> = line.split(',').collect();
-    let first_split: Vec<&str> = split[0].split('-').collect();
-    let second_split: Vec<&str> = split[1].split('-').collect();
+pub fn parse(input: &str) -> Result<Vec<Assignments>, ParseError> {
+    parse_lines_with(input, parse_assignments)
+}
+
+/// Allen's interval relations, of the first assignment of a pair to the second one.
+///
+/// An assignment is seen as the interval from the start of its first section to the end of its
+/// last one, so the assignments that meet are next to each other without sharing a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+impl Relation {
+    /// The relations, each one at the position of its inverse from the end.
+    pub const ALL: [Relation; 13] = [
+        Relation::Before,
+        Relation::Meets,
+        Relation::Overlaps,
+        Relation::Starts,
+        Relation::During,
+        Relation::Finishes,
+        Relation::Equals,
+        Relation::FinishedBy,
+        Relation::Contains,
+        Relation::StartedBy,
+        Relation::OverlappedBy,
+        Relation::MetBy,
+        Relation::After,
+    ];
+
+    pub fn classify(assignments: Assignments) -> Relation {
+        let (first_lower, first_upper, second_lower, second_upper) = assignments;
+        // The ends of the intervals, after the last sections.
+        let first_end = first_upper as i64 + 1;
+        let second_end = second_upper as i64 + 1;
+
+        match (
+            first_end.cmp(&(second_lower as i64)),
+            (first_lower as i64).cmp(&second_end),
+        ) {
+            (Ordering::Less, _) => return Relation::Before,
+            (Ordering::Equal, _) => return Relation::Meets,
+            (_, Ordering::Greater) => return Relation::After,
+            (_, Ordering::Equal) => return Relation::MetBy,
+            _ => {}
+        }
+        match (
+            first_lower.cmp(&second_lower),
+            first_upper.cmp(&second_upper),
+        ) {
+            (Ordering::Less, Ordering::Less) => Relation::Overlaps,
+            (Ordering::Less, Ordering::Equal) => Relation::FinishedBy,
+            (Ordering::Less, Ordering::Greater) => Relation::Contains,
+            (Ordering::Equal, Ordering::Less) => Relation::Starts,
+            (Ordering::Equal, Ordering::Equal) => Relation::Equals,
+            (Ordering::Equal, Ordering::Greater) => Relation::StartedBy,
+            (Ordering::Greater, Ordering::Less) => Relation::During,
+            (Ordering::Greater, Ordering::Equal) => Relation::Finishes,
+            (Ordering::Greater, Ordering::Greater) => Relation::OverlappedBy,
+        }
+    }
 
-    let first_lower = i32::from_str(first_split[0]).unwrap();
-    let first_upper = i32::from_str(first_split[1]).unwrap();
+    /// Returns the relation of the second assignment to the first one.
+    pub fn inverse(self) -> Relation {
+        Relation::ALL[Relation::ALL.len() - 1 - self as usize]
+    }
 
-    let second_lower = i32::from_str(second_split[0]).unwrap();
-    let second_upper = i32::from_str(second_split[1]).unwrap();
+    /// Whether one of the assignments contains the other one.
+    pub fn is_containment(self) -> bool {
+        matches!(
+            self,
+            Relation::Starts
+                | Relation::During
+                | Relation::Finishes
+                | Relation::Equals
+                | Relation::FinishedBy
+                | Relation::Contains
+                | Relation::StartedBy
+        )
+    }
 
-    if (first_lower <= second_lower && first_upper >= second_upper)
-        || (second_lower <= first_lower && second_upper >= first_upper)
-    {
-        return 1;
+    /// Whether the assignments share at least one section.
+    pub fn is_overlap(self) -> bool {
+        !matches!(
+            self,
+            Relation::Before | Relation::Meets | Relation::MetBy | Relation::After
+        )
     }
-    0
 }
 
-fn part1(input: &str) -> i32 {
-    process(input, &result1)
+/// The relation of every pair of assignments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Classification {
+    pub relations: Vec<Relation>,
+    counts: [usize; 13],
 }
 
-fn result2(line: &str) -> i32 {
-    let split: Vec<&str> = line.split(',').collect();
-    let first_split: Vec<&str> = split[0].split('-').collect();
-    let second_split: Vec<&str> = split[1].split('-').collect();
+impl Classification {
+    pub fn new(pairs: &[Assignments]) -> Self {
+        let relations: Vec<Relation> = pairs.iter().map(|&pair| Relation::classify(pair)).collect();
+        let mut counts = [0; 13];
+        for &relation in &relations {
+            counts[relation as usize] += 1;
+        }
+        Classification { relations, counts }
+    }
 
-    let first_lower = i32::from_str(first_split[0]).unwrap();
-    let first_upper = i32::from_str(first_split[1]).unwrap();
+    /// Returns the number of pairs in the relation.
+    pub fn count(&self, relation: Relation) -> usize {
+        self.counts[relation as usize]
+    }
 
-    let second_lower = i32::from_str(second_split[0]).unwrap();
-    let second_upper = i32::from_str(second_split[1]).unwrap();
+    /// Returns the number of pairs in the relations matching `predicate`.
+    pub fn count_where(&self, predicate: impl Fn(Relation) -> bool) -> usize {
+        Relation::ALL
+            .into_iter()
+            .filter(|&relation| predicate(relation))
+            .map(|relation| self.count(relation))
+            .sum()
+    }
 
-    let first_range = first_lower..=first_upper;
-    let second_range = second_lower..=second_upper;
+    /// Returns the 0-based indices of the pairs in the relation.
+    pub fn pairs(&self, relation: Relation) -> impl Iterator<Item = usize> + '_ {
+        self.relations
+            .iter()
+            .enumerate()
+            .filter(move |&(_, &other)| other == relation)
+            .map(|(i, _)| i)
+    }
+}
 
-    if first_range.contains(&second_lower)
-        || first_range.contains(&second_upper)
-        || second_range.contains(&first_lower)
-        || second_range.contains(&first_upper)
-    {
-        return 1;
+/// An elf, as the 0-based index of its pair and its position in the pair (0 or 1).
+pub type Elf = (usize, usize);
+
+/// The assignment of every elf.
+fn elves(pairs: &[Assignments]) -> impl Iterator<Item = (Elf, i32, i32)> + '_ {
+    pairs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &(a, b, c, d))| [((i, 0), a, b), ((i, 1), c, d)])
+}
+
+/// Consecutive sections assigned to the same number of elves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Run {
+    pub first: i32,
+    pub last: i32,
+    pub elves: usize,
+}
+
+/// The number of elves assigned to every section, from the first assigned section to the last
+/// one.
+///
+/// It is computed with a sweep over the ends of the assignments, so it only depends on the number
+/// of assignments and not on the number of sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    pub runs: Vec<Run>,
+}
+
+impl Coverage {
+    pub fn new(pairs: &[Assignments]) -> Self {
+        // Every assignment adds an elf at its first section, and removes it after its last one.
+        let mut events: Vec<(i64, i64)> = elves(pairs)
+            .flat_map(|(_, lower, upper)| [(lower as i64, 1), (upper as i64 + 1, -1)])
+            .collect();
+        events.sort_unstable();
+
+        let mut runs: Vec<Run> = vec![];
+        let mut elves = 0;
+        for (i, &(position, change)) in events.iter().enumerate() {
+            elves += change;
+            match events.get(i + 1) {
+                Some(&(next, _)) if next > position => {
+                    let last = (next - 1) as i32;
+                    match runs.last_mut() {
+                        Some(run) if run.elves == elves as usize => run.last = last,
+                        _ => runs.push(Run {
+                            first: position as i32,
+                            last,
+                            elves: elves as usize,
+                        }),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Coverage { runs }
+    }
+
+    /// Returns the number of elves assigned to the section.
+    pub fn elves_at(&self, section: i32) -> usize {
+        let i = self.runs.partition_point(|run| run.last < section);
+        match self.runs.get(i) {
+            Some(run) if run.first <= section => run.elves,
+            _ => 0,
+        }
+    }
+
+    /// Returns the maximum number of elves assigned to one section.
+    pub fn max_elves(&self) -> usize {
+        self.runs.iter().map(|run| run.elves).max().unwrap_or(0)
+    }
+
+    /// Returns the ranges of sections between the assigned ones that nobody covers.
+    pub fn uncovered(&self) -> Vec<(i32, i32)> {
+        self.runs
+            .iter()
+            .filter(|run| run.elves == 0)
+            .map(|run| (run.first, run.last))
+            .collect()
     }
-    0
 }
 
-fn part2(input: &str) -> i32 {
-    process(input, &result2)
+/// Returns the fewest elves whose assignments cover every assigned section, in order.
+///
+/// The uncovered section with the lowest ID is always covered by the elf reaching the furthest
+/// among the ones assigned to it.
+pub fn minimum_cover(pairs: &[Assignments]) -> Vec<Elf> {
+    let mut assignments: Vec<(Elf, i32, i32)> = elves(pairs).collect();
+    assignments.sort_unstable_by_key(|&(elf, lower, _)| (lower, elf));
+
+    let mut cover = vec![];
+    // The last covered section.
+    let mut covered = i64::MIN;
+    let mut i = 0;
+    loop {
+        while i < assignments.len() && assignments[i].2 as i64 <= covered {
+            i += 1;
+        }
+        let Some(&(_, lower, _)) = assignments.get(i) else {
+            break;
+        };
+        let section = (covered + 1).max(lower as i64);
+
+        let mut best = assignments[i];
+        while i < assignments.len() && assignments[i].1 as i64 <= section {
+            if assignments[i].2 > best.2 {
+                best = assignments[i];
+            }
+            i += 1;
+        }
+        cover.push(best.0);
+        covered = best.2 as i64;
+    }
+
+    cover.sort_unstable();
+    cover
+}
+
+pub fn solve1(pairs: &[Assignments]) -> i32 {
+    Classification::new(pairs).count_where(Relation::is_containment) as i32
+}
+
+pub fn part1(input: &str) -> Result<i32, ParseError> {
+    Ok(solve1(&parse(input)?))
+}
+
+pub fn solve2(pairs: &[Assignments]) -> i32 {
+    Classification::new(pairs).count_where(Relation::is_overlap) as i32
+}
+
+pub fn part2(input: &str) -> Result<i32, ParseError> {
+    Ok(solve2(&parse(input)?))
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 2);
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 584);
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 4);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 933);
+
+    let error = part2("2-4,6-8\n2-4,8-6").unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (2, 5, 3));
+    let error = part2("2-4,6-8\n2-4;6-8").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 3));
+}
+
+#[test]
+fn test_relations() {
+    let classification = Classification::new(&parse(&get_input(DAY, true)).unwrap());
+    assert_eq!(
+        classification.relations,
+        [
+            Relation::Before,
+            Relation::Meets,
+            Relation::Overlaps,
+            Relation::Contains,
+            Relation::Finishes,
+            Relation::Overlaps,
+        ]
+    );
+    assert_eq!(classification.count(Relation::Overlaps), 2);
+    assert_eq!(classification.count(Relation::After), 0);
+    assert_eq!(
+        classification.pairs(Relation::Overlaps).collect::<Vec<_>>(),
+        [2, 5]
+    );
+
+    assert_eq!(Relation::classify((3, 3, 3, 3)), Relation::Equals);
+    assert_eq!(Relation::classify((3, 5, 3, 4)), Relation::StartedBy);
+    assert_eq!(Relation::classify((4, 4, 3, 5)), Relation::During);
+    assert_eq!(Relation::classify((6, 9, 1, 5)), Relation::MetBy);
+    assert_eq!(
+        Relation::classify((i32::MAX, i32::MAX, i32::MIN, i32::MAX - 1)),
+        Relation::MetBy
+    );
+    for relation in Relation::ALL {
+        assert_eq!(relation.inverse().inverse(), relation);
+    }
+}
+
+#[test]
+fn test_coverage() {
+    let pairs = parse(&get_input(DAY, true)).unwrap();
+    let coverage = Coverage::new(&pairs);
+    let runs: Vec<(i32, i32, usize)> = coverage
+        .runs
+        .iter()
+        .map(|run| (run.first, run.last, run.elves))
+        .collect();
+    assert_eq!(
+        runs,
+        [
+            (2, 2, 4),
+            (3, 3, 5),
+            (4, 5, 7),
+            (6, 6, 8),
+            (7, 7, 6),
+            (8, 8, 4),
+            (9, 9, 1)
+        ]
+    );
+    assert_eq!(coverage.max_elves(), 8);
+    assert_eq!(coverage.elves_at(5), 7);
+    assert_eq!(coverage.elves_at(10), 0);
+    assert_eq!(coverage.uncovered(), []);
+    assert_eq!(minimum_cover(&pairs), [(2, 1), (3, 0)]);
+
+    let pairs = [(1, 2, 5, 6), (i32::MIN, i32::MIN, 2, i32::MAX)];
+    let coverage = Coverage::new(&pairs);
+    assert_eq!(coverage.uncovered(), [(i32::MIN + 1, 0)]);
+    assert_eq!(coverage.max_elves(), 2);
+    assert_eq!(coverage.elves_at(i32::MAX), 1);
+    assert_eq!(minimum_cover(&pairs), [(0, 0), (1, 0), (1, 1)]);
+
+    assert_eq!(Coverage::new(&[]).max_elves(), 0);
+    assert_eq!(minimum_cover(&[]), []);
+}
+
+#[cfg(test)]
+fn assignments_line((first_lower, first_upper, second_lower, second_upper): Assignments) -> String {
+    format!("{first_lower}-{first_upper},{second_lower}-{second_upper}")
+}
+
+/// Generates a pair of assignments, whose sections start between `lowest` and `highest`.
+#[cfg(test)]
+fn pair_strategy(lowest: i32, highest: i32) -> impl Strategy<Value = Assignments> {
+    let range = (lowest..highest, 0..20).prop_map(|(lower, length)| (lower, lower + length));
+    (range.clone(), range).prop_map(|((a, b), (c, d))| (a, b, c, d))
+}
+
+/// Generates pairs of assignments and the input listing them.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<Assignments>, String)> {
+    prop::collection::vec(pair_strategy(1, 100), 0..50).prop_map(|pairs| {
+        let lines: Vec<String> = pairs.iter().map(|&pair| assignments_line(pair)).collect();
+        (pairs, lines.join("\n"))
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_containment_implies_overlap((pairs, input) in input_strategy()) {
+        for &(a, b, c, d) in &pairs {
+            let relation = Relation::classify((a, b, c, d));
+            let contained = relation.is_containment() as i32;
+            let overlapping = relation.is_overlap() as i32;
+            prop_assert!(contained <= overlapping);
+
+            // Swapping the assignments inverts the relation.
+            prop_assert_eq!(Relation::classify((c, d, a, b)), relation.inverse());
+
+            // Brute force over the sections.
+            let first: Vec<i32> = (a..=b).collect();
+            let second: Vec<i32> = (c..=d).collect();
+            let shared = first.iter().filter(|section| second.contains(section)).count();
+            prop_assert_eq!(overlapping, (shared > 0) as i32);
+            prop_assert_eq!(contained, (shared == first.len().min(second.len())) as i32);
+        }
+
+        prop_assert!(part1(&input).unwrap() <= part2(&input).unwrap());
+        // Brute force over the sections.
+        let coverage = Coverage::new(&pairs);
+        let cover = minimum_cover(&pairs);
+        let assignments: Vec<(i32, i32)> = pairs
+            .iter()
+            .flat_map(|&(a, b, c, d)| [(a, b), (c, d)])
+            .collect();
+        for section in 0..130 {
+            let elves = assignments
+                .iter()
+                .filter(|&&(lower, upper)| (lower..=upper).contains(&section))
+                .count();
+            prop_assert_eq!(coverage.elves_at(section), elves);
+            let covered = cover.iter().any(|&(i, k)| {
+                let (lower, upper) = assignments[2 * i + k];
+                (lower..=upper).contains(&section)
+            });
+            prop_assert_eq!(covered, elves > 0);
+        }
+
+        let classification = Classification::new(&pairs);
+        let counts = Relation::ALL.into_iter().map(|relation| classification.count(relation));
+        prop_assert_eq!(counts.sum::<usize>(), pairs.len());
+        let overlapping = classification.count_where(Relation::is_overlap) as i32;
+        prop_assert_eq!(part2(&input).unwrap(), overlapping);
+    }
+
+    #[test]
+    fn property_minimum_cover(pairs in prop::collection::vec(pair_strategy(1, 30), 0..6)) {
+        // Brute force over every subset of the (at most 10) elves.
+        let assignments: Vec<(i32, i32)> = pairs
+            .iter()
+            .flat_map(|&(a, b, c, d)| [(a, b), (c, d)])
+            .collect();
+        let sections = |subset: u32| -> Vec<bool> {
+            (0..50)
+                .map(|section| {
+                    (0..assignments.len()).any(|elf| {
+                        let (lower, upper) = assignments[elf];
+                        subset & (1 << elf) != 0 && (lower..=upper).contains(&section)
+                    })
+                })
+                .collect()
+        };
+        let everyone = sections((1 << assignments.len()) - 1);
+        let smallest = (0..1_u32 << assignments.len())
+            .filter(|&subset| sections(subset) == everyone)
+            .map(u32::count_ones)
+            .min()
+            .unwrap();
+
+        let cover = minimum_cover(&pairs);
+        let subset = cover.iter().fold(0, |subset, &(i, k)| subset | 1 << (2 * i + k));
+        prop_assert_eq!(sections(subset), everyone);
+        prop_assert_eq!(cover.len(), smallest as usize);
+    }
 }
@@ -1,166 +1,856 @@
 // https://adventofcode.com/2022/day/5
 
 use crate::get_input;
-use regex::Regex;
-use std::str::FromStr;
-use std::usize;
-// use std::collections::HashSet;
+use crate::grid::Grid;
+use crate::parse::{parse_field, scan_fields, ParseError};
+use crate::render::Color;
+use crate::rope::{Rope, Ropes};
+use crate::simulation::Frames;
+use crate::terminal;
+use crate::trace;
+#[cfg(test)]
+use proptest::prelude::*;
+use std::error::Error;
 
 const DAY: u8 = 5;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-// fn process(input: &str, function: &dyn Fn(&str) -> i32) -> i32 {
-//     let mut score = 0;
-//     for line in input.lines() {
-//         let result = function(line);
-//         score += result;
-//     }
-//     score
-// }
-//
-// fn result1(line: &str) -> i32 {
-//     let split: Vec<&str> = line.split(',').collect();
-//     let first_split: Vec<&str> = split[0].split('-').collect();
-//     let second_split: Vec<&str> = split[1].split('-').collect();
-//
-//     let first_lower = i32::from_str(first_split[0]).unwrap();
-//     let first_upper = i32::from_str(first_split[1]).unwrap();
-//
-//     let second_lower = i32::from_str(second_split[0]).unwrap();
-//     let second_upper = i32::from_str(second_split[1]).unwrap();
-//
-//     if (first_lower <= second_lower && first_upper >= second_upper)
-//         || (second_lower <= first_lower && second_upper >= first_upper)
-//     {
-//         return 1;
-//     }
-//     0
-// }
+/// The stacks of crates, listed from the bottom. A crate is its label, drawn between brackets.
+pub type Stacks<'a> = Vec<Vec<&'a str>>;
 
-fn load_stack_line(line: &str, stacks: &mut [Vec<char>]) {
-    let mut index = 0;
-    loop {
-        let current_crate = line[index + 1..=index + 1].chars().next().unwrap();
+/// Returns the character columns (0-based, with the end excluded) of the labels of the stacks,
+/// checking that they are numbered from 1.
+///
+/// The crates are matched with the labels by characters rather than bytes, like `draw_stacks`
+/// aligns them, so that the labels of the crates can have any characters.
+fn parse_footer(line: &str) -> Result<Vec<(usize, usize)>, ParseError> {
+    let mut labels = vec![];
+    // The byte and the character column of the start of the current label.
+    let mut start = None;
+    let characters = line.char_indices().chain([(line.len(), ' ')]);
+    for (column, (i, c)) in characters.enumerate() {
+        match (start, c == ' ') {
+            (None, false) => start = Some((i, column)),
+            (Some((first, first_column)), true) => {
+                let label = &line[first..i];
+                let expected = (labels.len() + 1).to_string();
+                if label != expected {
+                    return Err(ParseError::new(
+                        first + 1,
+                        format!("expected stack {expected}, found `{label}`"),
+                    )
+                    .with_length(label.chars().count()));
+                }
+                labels.push((first_column, column));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if labels.is_empty() {
+        return Err(ParseError::new(1, "expected the numbers of the stacks")
+            .with_length(line.chars().count()));
+    }
+    Ok(labels)
+}
 
-        // dbg!(current_crate);
-        if !current_crate.is_whitespace() && !current_crate.is_numeric() {
-            stacks[index / 4].push(current_crate);
+/// Returns the (0-based) stack, the column and the label of every crate of a line of the
+/// drawing. A crate belongs to the stack whose number is under it.
+fn parse_crates<'a>(
+    line: &'a str,
+    labels: &[(usize, usize)],
+) -> Result<Vec<(usize, usize, &'a str)>, ParseError> {
+    // The errors have byte columns, the crates are matched with the labels by characters.
+    let characters: Vec<(usize, char)> = line.char_indices().collect();
+    let mut crates: Vec<(usize, usize, &str)> = vec![];
+    let mut j = 0;
+    while let Some(&(i, c)) = characters.get(j) {
+        if c == ' ' {
+            j += 1;
+            continue;
+        }
+        if c != '[' {
+            return Err(ParseError::new(
+                i + 1,
+                format!("expected `[` or a space, found `{c}`"),
+            ));
+        }
+
+        let Some(close) = (j..characters.len()).find(|&k| characters[k].1 == ']') else {
+            return Err(ParseError::new(i + 1, "expected `]` after the crate")
+                .with_length(characters.len() - j));
+        };
+        let label = &line[i + 1..characters[close].0];
+        let length = close - j + 1;
+        if label.is_empty() || label.contains([' ', '[']) {
+            return Err(
+                ParseError::new(i + 1, "expected the label of the crate").with_length(length)
+            );
         }
 
-        index += 4;
-        if index >= line.len() - 1 {
-            // dbg!(index, line.len());
-            break;
+        let stacks: Vec<usize> = (0..labels.len())
+            .filter(|&stack| labels[stack].0 <= close && labels[stack].1 > j)
+            .collect();
+        match stacks[..] {
+            [stack] if crates.last().is_none_or(|&(other, _, _)| other != stack) => {
+                crates.push((stack, i + 1, label));
+            }
+            [stack] => {
+                return Err(ParseError::new(
+                    i + 1,
+                    format!("expected one crate of stack {} on the line", stack + 1),
+                )
+                .with_length(length));
+            }
+            _ => {
+                return Err(ParseError::new(
+                    i + 1,
+                    "expected the crate to be above the number of a stack",
+                )
+                .with_length(length));
+            }
         }
+        j = close + 1;
     }
+    Ok(crates)
 }
 
-fn reverse_stacks(stacks: &mut [Vec<char>]) {
-    for stack in stacks {
-        stack.reverse();
+/// Reads the drawing of the stacks, whose last line has the numbers of the stacks.
+fn parse_stacks<'a>(lines: &[&'a str]) -> Result<Stacks<'a>, ParseError> {
+    let Some((&footer, drawing)) = lines.split_last() else {
+        return Err(ParseError::new(1, "expected the drawing of the stacks").on_line(1));
+    };
+    let labels = parse_footer(footer).map_err(|error| error.in_line(lines.len(), footer))?;
+
+    let mut stacks: Stacks = vec![vec![]; labels.len()];
+    // Whether every crate of the stack has been found so far, from the bottom.
+    let mut complete = vec![true; labels.len()];
+    for (i, &line) in drawing.iter().enumerate().rev() {
+        let crates = parse_crates(line, &labels).map_err(|error| error.in_line(i + 1, line))?;
+        let mut found = vec![false; labels.len()];
+        for (stack, column, label) in crates {
+            if !complete[stack] {
+                return Err(ParseError::new(column, "expected a crate under this one")
+                    .with_length(label.chars().count() + 2)
+                    .in_line(i + 1, line));
+            }
+            stacks[stack].push(label);
+            found[stack] = true;
+        }
+        for (complete, found) in complete.iter_mut().zip(found) {
+            *complete &= found;
+        }
+    }
+    Ok(stacks)
+}
+
+/// Draws the stacks in the format of the puzzle, with the numbers of the stacks under them.
+///
+/// The crates are as wide as the longest label, so that the drawing can be read again.
+pub fn draw_stacks<S: AsRef<str>>(stacks: &[Vec<S>]) -> String {
+    let label_width = stacks
+        .iter()
+        .flatten()
+        .map(|label| label.as_ref().chars().count())
+        .max()
+        .unwrap_or(1);
+    let width = (label_width + 2).max(stacks.len().to_string().len());
+
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+    let mut lines = vec![];
+    for level in (0..height).rev() {
+        let crates: Vec<String> = stacks
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(label) => format!("{:<width$}", format!("[{}]", label.as_ref())),
+                None => " ".repeat(width),
+            })
+            .collect();
+        lines.push(crates.join(" ").trim_end().to_string());
     }
+    let numbers: Vec<String> = (1..=stacks.len()).map(|i| format!("{i:^width$}")).collect();
+    lines.push(numbers.join(" ").trim_end().to_string());
+    lines.join("\n")
+}
+
+/// A move of crates between two (0-based) stacks, with the line it was read from.
+pub struct Instruction<'a> {
+    line: usize,
+    text: &'a str,
+    number_of_crates: usize,
+    /// The column and the length of the number of crates, for the errors.
+    crates_field: (usize, usize),
+    from: usize,
+    to: usize,
 }
 
-fn process_with_single_move(stacks: &mut Vec<Vec<char>>, instructions: Vec<&str>) {
-    let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
+/// The stacks of crates, listed from the bottom, and the moves of the crane.
+pub struct Procedure<'a> {
+    stacks: Stacks<'a>,
+    instructions: Vec<Instruction<'a>>,
+}
+
+impl<'a> Procedure<'a> {
+    pub fn stacks(&self) -> &[Vec<&'a str>] {
+        &self.stacks
+    }
 
-    for instruction in instructions {
-        let captures = re.captures_iter(instruction).last().unwrap();
-        let number_of_crates = i32::from_str(&captures[1]).unwrap();
-        let from = usize::from_str(&captures[2]).unwrap() - 1;
-        let to = usize::from_str(&captures[3]).unwrap() - 1;
+    /// Returns the line and the text of every move.
+    pub fn moves(&self) -> impl Iterator<Item = (usize, &'a str)> + '_ {
+        self.instructions
+            .iter()
+            .map(|instruction| (instruction.line, instruction.text))
+    }
+}
 
-        for _ in 0..number_of_crates {
-            let current_crate = stacks[from].pop().unwrap();
-            stacks[to].push(current_crate);
+/// Returns the number of crates and the (0-based) stacks of a move, checking that the stacks
+/// exist.
+fn parse_instruction(
+    stacks_number: usize,
+    line: usize,
+    text: &str,
+) -> Result<Instruction<'_>, ParseError> {
+    let fields = scan_fields(text, "move {} from {} to {}")?;
+    let stack = |(column, text): (usize, &str)| {
+        let index = parse_field::<usize>(Some((column, text)))?;
+        if !(1..=stacks_number).contains(&index) {
+            return Err(ParseError::new(
+                column,
+                format!("expected a stack from 1 to {stacks_number}"),
+            )
+            .with_length(text.len()));
         }
+        Ok(index - 1)
+    };
+
+    Ok(Instruction {
+        line,
+        text,
+        number_of_crates: parse_field(Some(fields[0]))?,
+        crates_field: (fields[0].0, fields[0].1.len()),
+        from: stack(fields[1])?,
+        to: stack(fields[2])?,
+    })
+}
+
+/// The order of the moved crates, compared to the one they had on their stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Kept,
+    Reversed,
+}
+
+/// A crane, which lifts crates from the top of a stack and puts them on another one.
+///
+/// Every move calls either `order` or, when it returns `None`, `arrange`.
+pub trait Crane {
+    /// Checks that the crane can lift that many crates, returning the problem otherwise.
+    fn check(&self, _number_of_crates: usize) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Returns the order of the crates of the next move, if it keeps or reverses them. The
+    /// crates are then moved together, so that the move costs the same whatever their number.
+    fn order(&mut self, _number_of_crates: usize) -> Option<Order> {
+        None
     }
+
+    /// Puts the lifted crates, listed from the bottom, in the order they end up on the other
+    /// stack.
+    fn arrange(&mut self, crates: &mut [&str]);
 }
 
-fn process_with_multiple_moves(stacks: &mut Vec<Vec<char>>, instructions: Vec<&str>) {
-    let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
+/// The crane of the first part, which moves one crate at a time.
+pub struct CrateMover9000;
 
-    for instruction in instructions {
-        let captures = re.captures_iter(instruction).last().unwrap();
-        let number_of_crates = usize::from_str(&captures[1]).unwrap();
-        let from = usize::from_str(&captures[2]).unwrap() - 1;
-        let to = usize::from_str(&captures[3]).unwrap() - 1;
+impl Crane for CrateMover9000 {
+    fn order(&mut self, _number_of_crates: usize) -> Option<Order> {
+        Some(Order::Reversed)
+    }
+
+    fn arrange(&mut self, crates: &mut [&str]) {
+        crates.reverse();
+    }
+}
 
-        // This can probably be done much more efficient, but I don't know how to use vector slices.
-        let mut temporary_stack: Vec<char> = vec![];
-        for _ in 0..number_of_crates {
-            temporary_stack.push(stacks[from].pop().unwrap());
+/// The crane of the second part, which moves all the crates at once.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn order(&mut self, _number_of_crates: usize) -> Option<Order> {
+        Some(Order::Kept)
+    }
+
+    fn arrange(&mut self, _crates: &mut [&str]) {}
+}
+
+/// A crane which cannot lift more than `capacity` crates at once.
+pub struct Limited<C> {
+    pub crane: C,
+    pub capacity: usize,
+}
+
+impl<C: Crane> Crane for Limited<C> {
+    fn check(&self, number_of_crates: usize) -> Result<(), String> {
+        if number_of_crates > self.capacity {
+            return Err(format!(
+                "expected at most {} crates, the capacity of the crane",
+                self.capacity
+            ));
+        }
+        self.crane.check(number_of_crates)
+    }
+
+    fn order(&mut self, number_of_crates: usize) -> Option<Order> {
+        self.crane.order(number_of_crates)
+    }
+
+    fn arrange(&mut self, crates: &mut [&str]) {
+        self.crane.arrange(crates);
+    }
+}
+
+/// A crane which reverses the crates of every other move, starting with the first one.
+#[derive(Default)]
+pub struct Alternating {
+    moves: usize,
+}
+
+impl Crane for Alternating {
+    fn order(&mut self, _number_of_crates: usize) -> Option<Order> {
+        self.moves += 1;
+        match self.moves % 2 {
+            1 => Some(Order::Reversed),
+            _ => Some(Order::Kept),
         }
-        temporary_stack.reverse();
-        for current_crate in temporary_stack {
-            stacks[to].push(current_crate);
+    }
+
+    fn arrange(&mut self, crates: &mut [&str]) {
+        if self.order(crates.len()) == Some(Order::Reversed) {
+            crates.reverse();
+        }
+    }
+}
+
+/// Does the move with the crane, checking that the stack has enough crates and that the crane
+/// can lift them.
+fn process(
+    ropes: &mut Ropes<&str>,
+    stacks: &mut [Rope],
+    instruction: &Instruction,
+    crane: &mut dyn Crane,
+) -> Result<(), ParseError> {
+    let (column, length) = instruction.crates_field;
+    let error = |message: String| {
+        ParseError::new(column, message)
+            .with_length(length)
+            .in_line(instruction.line, instruction.text)
+    };
+
+    let height = ropes.len(stacks[instruction.from]);
+    if instruction.number_of_crates > height {
+        return Err(error(format!(
+            "expected at most {height} crates, the height of stack {}",
+            instruction.from + 1
+        )));
+    }
+    crane.check(instruction.number_of_crates).map_err(error)?;
+
+    let crates = ropes.split_off(
+        &mut stacks[instruction.from],
+        height - instruction.number_of_crates,
+    );
+    match crane.order(instruction.number_of_crates) {
+        Some(Order::Kept) => {}
+        Some(Order::Reversed) => ropes.reverse(crates),
+        None => {
+            let mut lifted: Vec<&str> = ropes.values(crates).into_iter().copied().collect();
+            crane.arrange(&mut lifted);
+            ropes.set_values(crates, lifted);
+        }
+    }
+    ropes.append(&mut stacks[instruction.to], crates);
+    Ok(())
+}
+
+fn crate_color(cell: &char) -> Color {
+    match cell {
+        '.' => [20, 20, 30],
+        _ => [200, 150, 80],
+    }
+}
+
+/// Draws the stacks side by side, from the bottom of a grid as high as all the crates together.
+/// Every crate is shown with the first character of its label.
+fn stacks_grid(stacks: &[Vec<&str>], height: usize) -> Grid<char> {
+    Grid::from_fn(stacks.len(), height, |x, y| {
+        match stacks[x].get(height - 1 - y) {
+            Some(label) => label.chars().next().unwrap(),
+            None => '.',
         }
+    })
+}
+
+/// Returns the crates of every stack, from the bottom.
+fn read_stacks<'a>(ropes: &Ropes<&'a str>, stacks: &[Rope]) -> Stacks<'a> {
+    stacks
+        .iter()
+        .map(|&stack| ropes.values(stack).into_iter().copied().collect())
+        .collect()
+}
+
+/// Draws the stacks, keeping the top of the highest one visible.
+fn record(frames: &mut Frames<char>, ropes: &Ropes<&str>, stacks: &[Rope], height: usize) {
+    if !frames.is_active() {
+        return;
     }
+    let highest = stacks
+        .iter()
+        .map(|&stack| ropes.len(stack))
+        .max()
+        .unwrap_or(0);
+    let focus = (0, height.saturating_sub(highest.max(1)));
+    frames.frame(focus, || stacks_grid(&read_stacks(ropes, stacks), height));
 }
 
-fn show_stack_tops(stacks: Vec<Vec<char>>) -> String {
-    let mut result: Vec<String> = vec![];
+fn show_stack_tops(stacks: &[Vec<&str>]) -> String {
+    stacks
+        .iter()
+        .filter_map(|stack| stack.last().copied())
+        .collect()
+}
 
-    for stack in stacks {
-        let current_crate = stack.last().unwrap_or(&' ');
-        if !current_crate.is_whitespace() {
-            result.push(current_crate.to_string());
+pub fn parse(input: &str) -> Result<Procedure<'_>, ParseError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let blank = lines
+        .iter()
+        .position(|line| line.is_empty())
+        .unwrap_or(lines.len());
+    let stacks = parse_stacks(&lines[..blank])?;
+
+    let instructions = lines
+        .iter()
+        .enumerate()
+        .skip(blank + 1)
+        .map(|(i, &text)| {
+            parse_instruction(stacks.len(), i + 1, text).map_err(|error| error.in_line(i + 1, text))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Procedure {
+        stacks,
+        instructions,
+    })
+}
+
+/// Returns the stacks as ropes, so that the crates on their top are moved at once.
+fn build_ropes<'a>(stacks: &[Vec<&'a str>]) -> (Ropes<&'a str>, Vec<Rope>) {
+    let mut ropes = Ropes::new();
+    let mut built = vec![Rope::EMPTY; stacks.len()];
+    for (rope, stack) in built.iter_mut().zip(stacks) {
+        for &label in stack {
+            ropes.push(rope, label);
         }
     }
+    (ropes, built)
+}
 
-    result.join("")
+/// Returns the stacks after all the moves of the crane. `visit` is called with the number of
+/// moves done and the stacks, before the first move and after every move.
+fn rearrange<'a>(
+    procedure: &Procedure<'a>,
+    name: &str,
+    crane: &mut dyn Crane,
+    mut visit: impl FnMut(usize, &Ropes<&'a str>, &[Rope]),
+) -> Result<Stacks<'a>, ParseError> {
+    let (mut ropes, mut stacks) = build_ropes(&procedure.stacks);
+
+    let height = procedure.stacks.iter().map(Vec::len).sum();
+    let mut frames = Frames::new(
+        None,
+        terminal::animation(name, |&cell| cell, crate_color),
+        trace::recorder(name, |&cell| cell, crate_color),
+    );
+    record(&mut frames, &ropes, &stacks, height);
+    visit(0, &ropes, &stacks);
+    for (i, instruction) in procedure.instructions.iter().enumerate() {
+        process(&mut ropes, &mut stacks, instruction, crane)?;
+        record(&mut frames, &ropes, &stacks, height);
+        visit(i + 1, &ropes, &stacks);
+    }
+
+    Ok(read_stacks(&ropes, &stacks))
+}
+
+/// Returns the stacks after the moves of any crane.
+pub fn rearrange_with<'a>(
+    procedure: &Procedure<'a>,
+    crane: &mut dyn Crane,
+) -> Result<Stacks<'a>, ParseError> {
+    rearrange(procedure, "day05_crane", crane, |_, _, _| {})
+}
+
+/// Does the moves of any crane, calling `visit` with the stacks after the numbers of moves
+/// selected by `shown` (0 for the initial stacks), and returns the final stacks.
+pub fn replay<'a>(
+    procedure: &Procedure<'a>,
+    crane: &mut dyn Crane,
+    shown: impl Fn(usize) -> bool,
+    mut visit: impl FnMut(usize, Stacks<'a>),
+) -> Result<Stacks<'a>, ParseError> {
+    rearrange(procedure, "day05_replay", crane, |moves, ropes, stacks| {
+        if shown(moves) {
+            visit(moves, read_stacks(ropes, stacks));
+        }
+    })
 }
 
-fn task(input: &str, processing_function: &dyn Fn(&mut Vec<Vec<char>>, Vec<&str>)) -> String {
-    let mut stack_loaded = false;
+/// Returns the initial stacks, by undoing the moves of the procedure from the final stacks.
+///
+/// The crane has to be in the state it had before the procedure, so that it chooses the same
+/// orders, and has to keep or reverse the crates of every move. These moves are their own
+/// inverses, so they are done again from the other stack.
+pub fn undo<'a>(
+    procedure: &Procedure,
+    stacks: &[Vec<&'a str>],
+    crane: &mut dyn Crane,
+) -> Result<Stacks<'a>, ParseError> {
+    if stacks.len() != procedure.stacks.len() {
+        return Err(ParseError::new(
+            1,
+            format!(
+                "expected {} stacks, found {}",
+                procedure.stacks.len(),
+                stacks.len()
+            ),
+        )
+        .on_line(1));
+    }
+    let mut orders = Vec::with_capacity(procedure.instructions.len());
+    for instruction in &procedure.instructions {
+        match crane.order(instruction.number_of_crates) {
+            Some(order) => orders.push(order),
+            None => {
+                return Err(ParseError::whole_line(
+                    instruction.line,
+                    instruction.text,
+                    "expected a move which keeps or reverses the crates, to be undone",
+                ))
+            }
+        }
+    }
 
-    let mut stacks: Vec<Vec<char>> = vec![vec![]; 10];
-    let mut instructions: Vec<&str> = vec![];
+    let (mut ropes, mut stacks) = build_ropes(stacks);
+    for (instruction, order) in procedure.instructions.iter().zip(orders).rev() {
+        let height = ropes.len(stacks[instruction.to]);
+        if instruction.number_of_crates > height {
+            let (column, length) = instruction.crates_field;
+            return Err(ParseError::new(
+                column,
+                format!(
+                    "expected at most {height} crates, the height of stack {} before undoing \
+                     the move",
+                    instruction.to + 1
+                ),
+            )
+            .with_length(length)
+            .in_line(instruction.line, instruction.text));
+        }
 
-    for line in input.lines() {
-        if line.is_empty() {
-            stack_loaded = true;
-        } else if stack_loaded {
-            instructions.push(line);
-        } else {
-            load_stack_line(line, &mut stacks);
+        let crates = ropes.split_off(
+            &mut stacks[instruction.to],
+            height - instruction.number_of_crates,
+        );
+        if order == Order::Reversed {
+            ropes.reverse(crates);
         }
+        ropes.append(&mut stacks[instruction.from], crates);
     }
+    Ok(read_stacks(&ropes, &stacks))
+}
 
-    reverse_stacks(&mut stacks);
-    processing_function(&mut stacks, instructions);
+pub fn rearrange1<'a>(procedure: &Procedure<'a>) -> Result<Stacks<'a>, ParseError> {
+    rearrange(procedure, "day05_part1", &mut CrateMover9000, |_, _, _| {})
+}
+
+pub fn rearrange2<'a>(procedure: &Procedure<'a>) -> Result<Stacks<'a>, ParseError> {
+    rearrange(procedure, "day05_part2", &mut CrateMover9001, |_, _, _| {})
+}
 
-    show_stack_tops(stacks)
+pub fn solve1(procedure: &Procedure) -> Result<String, ParseError> {
+    Ok(show_stack_tops(&rearrange1(procedure)?))
 }
 
-fn part1(input: &str) -> String {
-    task(input, &process_with_single_move)
+pub fn solve2(procedure: &Procedure) -> Result<String, ParseError> {
+    Ok(show_stack_tops(&rearrange2(procedure)?))
 }
 
-fn part2(input: &str) -> String {
-    task(input, &process_with_multiple_moves)
+pub fn part1(input: &str) -> Result<String, ParseError> {
+    solve1(&parse(input)?)
+}
+
+pub fn part2(input: &str) -> Result<String, ParseError> {
+    solve2(&parse(input)?)
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, "CMZ");
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, "NTWZZWHFV");
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, "MCD");
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, "BRZGFVBTJ");
+
+    let input =
+        "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n\nmove 1 from 2 to 1\nmove 3 from 1 to 4";
+    let error = part2(input).unwrap_err();
+    assert_eq!((error.line, error.column), (7, 18));
+    assert_eq!(error.message, "expected a stack from 1 to 3");
+
+    let input = "[A]\n 1\n\nmove 2 from 1 to 1";
+    let error = part2(input).unwrap_err();
+    assert_eq!((error.line, error.column), (4, 6));
+}
+
+/// A crane which swaps the crates two by two, from the bottom.
+#[cfg(test)]
+struct EveryOther;
+
+#[cfg(test)]
+impl Crane for EveryOther {
+    fn arrange(&mut self, crates: &mut [&str]) {
+        for pair in crates.chunks_mut(2) {
+            pair.reverse();
+        }
+    }
+}
+
+#[test]
+fn test_cranes() {
+    let input = get_input(DAY, true);
+    let procedure = parse(&input).unwrap();
+    let tops = |crane: &mut dyn Crane| {
+        rearrange_with(&procedure, crane).map(|stacks| show_stack_tops(&stacks))
+    };
+    assert_eq!(tops(&mut CrateMover9000).unwrap(), "CMZ");
+    assert_eq!(tops(&mut CrateMover9001).unwrap(), "MCD");
+    // The first and third moves are done like with the first crane, the others like with the
+    // second one.
+    assert_eq!(tops(&mut Alternating::default()).unwrap(), "CMD");
+    // The crates of the second move end as "N Z D" on the third stack, those of the third one
+    // as "C M" on the first stack.
+    assert_eq!(tops(&mut EveryOther).unwrap(), "CMD");
+
+    let mut crane = Limited {
+        crane: CrateMover9001,
+        capacity: 2,
+    };
+    let error = rearrange_with(&procedure, &mut crane).unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (7, 6, 1));
+    assert_eq!(
+        error.message,
+        "expected at most 2 crates, the capacity of the crane"
+    );
+    crane.capacity = 3;
+    assert_eq!(
+        show_stack_tops(&rearrange_with(&procedure, &mut crane).unwrap()),
+        "MCD"
+    );
+}
+
+#[test]
+fn test_replay() {
+    let input = get_input(DAY, true);
+    let procedure = parse(&input).unwrap();
+    let mut drawings = vec![];
+    let stacks = replay(
+        &procedure,
+        &mut CrateMover9000,
+        |moves| moves == 1,
+        |moves, stacks| drawings.push((moves, draw_stacks(&stacks))),
+    )
+    .unwrap();
+    assert_eq!(
+        drawings,
+        [(1, "[D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3".to_string())]
+    );
+    assert_eq!(procedure.moves().nth(1), Some((7, "move 3 from 1 to 3")));
+
+    let initial = undo(&procedure, &stacks, &mut CrateMover9000).unwrap();
+    assert_eq!(draw_stacks(&initial), draw_stacks(procedure.stacks()));
+    let stacks = rearrange_with(&procedure, &mut Alternating::default()).unwrap();
+    let initial = undo(&procedure, &stacks, &mut Alternating::default()).unwrap();
+    assert_eq!(initial, procedure.stacks);
+    let stacks = rearrange_with(&procedure, &mut EveryOther).unwrap();
+    let error = undo(&procedure, &stacks, &mut EveryOther).unwrap_err();
+    assert_eq!((error.line, error.column), (6, 1));
+
+    // The last move took a crate from the first stack, so it has to be on the second one.
+    let mut stacks = rearrange2(&procedure).unwrap();
+    stacks[1].clear();
+    let error = undo(&procedure, &stacks, &mut CrateMover9001).unwrap_err();
+    assert_eq!((error.line, error.column), (9, 6));
+    assert_eq!(
+        undo(&procedure, &stacks[1..], &mut CrateMover9001)
+            .unwrap_err()
+            .message,
+        "expected 3 stacks, found 2"
+    );
+}
+
+#[test]
+fn test_drawing() {
+    let input = get_input(DAY, true);
+    let procedure = parse(&input).unwrap();
+    let drawing: Vec<&str> = input.lines().take(4).collect();
+    assert_eq!(draw_stacks(&procedure.stacks), drawing.join("\n"));
+    assert_eq!(
+        draw_stacks(&rearrange2(&procedure).unwrap()),
+        "        [D]\n        [N]\n        [Z]\n[M] [C] [P]\n 1   2   3"
+    );
+
+    let procedure = parse("[AB]   \n[C]  [DE]\n 1    2   \n\nmove 1 from 1 to 2").unwrap();
+    assert_eq!(procedure.stacks, [vec!["C", "AB"], vec!["DE"]]);
+    assert_eq!(solve1(&procedure).unwrap(), "CAB");
+    let stacks = [vec!["éé"], vec!["x"]];
+    let drawing = draw_stacks(&stacks);
+    assert_eq!(drawing, "[éé] [x]\n 1    2");
+    assert_eq!(parse(&drawing).unwrap().stacks, stacks);
+    let error = parse("[é]   [x]\n 1   2").err().unwrap();
+    assert_eq!((error.column, error.length), (8, 3));
+    assert!(error.to_string().ends_with("1 | [é]   [x]\n  |       ^^^"));
+
+    let stacks: Vec<Vec<String>> = (1..=10).map(|i| vec![i.to_string()]).collect();
+    let drawing = draw_stacks(&stacks);
+    assert!(drawing.ends_with("[10]\n 1    2    3    4    5    6    7    8    9    10"));
+    assert_eq!(parse(&drawing).unwrap().stacks, stacks);
+
+    for (input, line, column, message) in [
+        ("[A]\n 1  3", 2, 5, "expected stack 2, found `3`"),
+        (
+            "    [B]\n[A]\n 1   2",
+            1,
+            5,
+            "expected a crate under this one",
+        ),
+        (
+            "[A]     [B]\n 1   2",
+            1,
+            9,
+            "expected the crate to be above the number of a stack",
+        ),
+        ("[A] B\n 1   2", 1, 5, "expected `[` or a space, found `B`"),
+        ("[A\n 1", 1, 1, "expected `]` after the crate"),
+        ("", 1, 1, "expected the drawing of the stacks"),
+    ] {
+        let error = parse(input).err().unwrap();
+        assert_eq!(
+            (error.line, error.column, error.message.as_str()),
+            (line, column, message)
+        );
+    }
+}
+
+/// A move of crates between two (1-based) stacks.
+#[cfg(test)]
+type Move = (usize, usize, usize);
+
+/// Draws the stacks (listed from the bottom) and the moves in the format of the puzzle.
+#[cfg(test)]
+fn drawing(stacks: &[Vec<String>], moves: &[Move]) -> String {
+    let mut lines = vec![draw_stacks(stacks), String::new()];
+    for (number_of_crates, from, to) in moves {
+        lines.push(format!("move {number_of_crates} from {from} to {to}"));
+    }
+    lines.join("\n")
+}
+
+/// Generates stacks of crates and moves that can all be done.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<Vec<String>>, Vec<Move>)> {
+    // Labels with characters of several bytes check that the crates are aligned by characters.
+    let label = "[A-Zα-ω]{1,3}";
+    let stacks = prop::collection::vec(prop::collection::vec(label, 0..6), 1..12)
+        .prop_filter("no crates", |stacks| {
+            stacks.iter().any(|stack| !stack.is_empty())
+        });
+    let seeds = prop::collection::vec((0..100_usize, 0..100_usize, 0..100_usize), 0..30);
+
+    (stacks, seeds).prop_map(|(stacks, seeds)| {
+        // The seeds are turned into valid moves by following the heights of the stacks.
+        let mut heights: Vec<usize> = stacks.iter().map(Vec::len).collect();
+        let mut moves = vec![];
+        for (count, from, to) in seeds {
+            let non_empty: Vec<usize> = (0..heights.len()).filter(|&i| heights[i] > 0).collect();
+            let from = non_empty[from % non_empty.len()];
+            let to = to % heights.len();
+            let count = count % heights[from] + 1;
+            heights[from] -= count;
+            heights[to] += count;
+            moves.push((count, from + 1, to + 1));
+        }
+        (stacks, moves)
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_single_crate_moves((stacks, moves) in input_strategy()) {
+        // When the crates are moved one at a time, both cranes do the same.
+        let moves: Vec<Move> = moves
+            .into_iter()
+            .flat_map(|(count, from, to)| std::iter::repeat_n((1, from, to), count))
+            .collect();
+        let input = drawing(&stacks, &moves);
+        prop_assert_eq!(part1(&input).unwrap(), part2(&input).unwrap());
+    }
+
+    #[test]
+    fn property_moves_are_undone((stacks, moves) in input_strategy()) {
+        // The second crane keeps the order of the crates, so moving them back restores the stacks.
+        let tops = part2(&drawing(&stacks, &[])).unwrap();
+        let undone: Vec<Move> = moves.iter().rev().map(|&(count, from, to)| (count, to, from)).collect();
+        let all_moves: Vec<Move> = moves.iter().copied().chain(undone).collect();
+        prop_assert_eq!(part2(&drawing(&stacks, &all_moves)).unwrap(), tops);
+
+        // Every stack that is not empty shows its top crate.
+        let result = part1(&drawing(&stacks, &moves)).unwrap();
+        prop_assert!(!result.is_empty());
+
+        // The crates are only moved, and the drawing of the final stacks reads the same.
+        let input = drawing(&stacks, &moves);
+        let rearranged = rearrange1(&parse(&input).unwrap()).unwrap();
+        let mut before: Vec<&String> = stacks.iter().flatten().collect();
+        let mut after: Vec<&str> = rearranged.iter().flatten().copied().collect();
+        before.sort();
+        after.sort();
+        prop_assert!(before.iter().map(|label| label.as_str()).eq(after));
+        let redrawn = draw_stacks(&rearranged);
+        prop_assert_eq!(parse(&redrawn).unwrap().stacks, rearranged);
+
+        // Undoing the moves of both cranes gives back the initial drawing.
+        let procedure = parse(&input).unwrap();
+        for crane in [&mut CrateMover9000 as &mut dyn Crane, &mut CrateMover9001] {
+            let rearranged = rearrange_with(&procedure, crane).unwrap();
+            let initial = undo(&procedure, &rearranged, crane).unwrap();
+            prop_assert_eq!(draw_stacks(&initial), draw_stacks(&stacks));
+        }
+    }
 }
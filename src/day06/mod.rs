@@ -1,7 +1,14 @@
 // https://adventofcode.com/2022/day/6
 
+use crate::bit_set::CharSet;
 use crate::get_input;
+use crate::parse::ParseError;
+use crate::stream::StreamError;
+#[cfg(test)]
+use proptest::prelude::*;
 use std::collections::VecDeque;
+use std::error::Error;
+use std::io::BufRead;
 
 const DAY: u8 = 6;
 
@@ -13,61 +20,208 @@ const TEST_INPUTS: [(&str, i32, i32); 5] = [
     ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
 ];
 
-pub fn main() {
+pub fn main() -> Result<(), Box<dyn Error>> {
     for input in TEST_INPUTS {
-        println!("{}", part1(input.0));
+        println!("{}", part1(input.0)?);
     }
-    println!("{}", part1(&get_input(DAY, false)));
+    println!("{}", part1(&get_input(DAY, false))?);
     for input in TEST_INPUTS {
-        println!("{}", part1(input.0));
+        println!("{}", part1(input.0)?);
     }
-    println!("{}", part2(&get_input(DAY, false)));
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-fn find_unique_index(input: &str, unique_chars: usize) -> i32 {
-    let mut sequence: VecDeque<char> = VecDeque::new();
-    let mut index = 0;
+/// Returns the datastream, checking that it only has letters.
+pub fn parse(input: &str) -> Result<&str, ParseError> {
+    let input = input.trim_end();
+    if let Some((i, letter)) = input
+        .char_indices()
+        .find(|(_, c)| CharSet::index(*c).is_none())
+    {
+        return Err(
+            ParseError::new(i + 1, format!("expected a letter, found `{letter}`"))
+                .in_line(1, input),
+        );
+    }
+    Ok(input)
+}
+
+/// The search for the first window of `size` different letters, which only keeps the letters of
+/// the window.
+struct Marker {
+    size: usize,
+    letters: VecDeque<char>,
+    // Every letter in the window is toggled, so the set has as many letters as the window only
+    // if each of them appears once.
+    window: CharSet,
+}
+
+impl Marker {
+    fn new(size: usize) -> Self {
+        Marker {
+            size,
+            letters: VecDeque::with_capacity(size + 1),
+            window: CharSet::new(),
+        }
+    }
+
+    /// Adds the next letter of the datastream, and returns whether the window is a marker.
+    fn push(&mut self, letter: char) -> bool {
+        self.window.toggle(letter);
+        self.letters.push_back(letter);
+        if self.letters.len() > self.size {
+            self.window.toggle(self.letters.pop_front().unwrap());
+        }
+
+        self.window.len() == self.size
+    }
 
+    fn missing(&self, length: usize) -> ParseError {
+        ParseError::new(
+            1,
+            format!("expected {} different letters in a row", self.size),
+        )
+        .with_length(length)
+        .on_line(1)
+    }
+}
+
+fn find_unique_index(input: &str, unique_chars: usize) -> Result<i32, ParseError> {
+    let mut marker = Marker::new(unique_chars);
     for (i, letter) in input.chars().enumerate() {
-        while sequence.contains(&letter) {
-            sequence.pop_front();
+        if marker.push(letter) {
+            return Ok(i as i32 + 1);
         }
-        sequence.push_back(letter);
+    }
+
+    Err(marker.missing(input.chars().count()).in_line(1, input))
+}
+
+/// Returns the answers of both parts, reading the datastream one letter at a time. The reading
+/// stops at the second marker (which is never before the first one) or at the end of the line.
+pub fn stream(reader: impl BufRead) -> Result<(u64, u64), StreamError> {
+    let mut markers = [Marker::new(4), Marker::new(14)];
+    let mut found = [None, None];
+    let mut position = 0;
 
-        if sequence.len() == unique_chars {
-            index = i as i32 + 1;
+    for byte in reader.bytes() {
+        let letter = byte? as char;
+        if letter == '\n' || letter == '\r' {
             break;
         }
+        position += 1;
+        if CharSet::index(letter).is_none() {
+            let error = ParseError::new(position, format!("expected a letter, found `{letter}`"));
+            return Err(error.on_line(1).into());
+        }
+
+        for (marker, found) in markers.iter_mut().zip(&mut found) {
+            if found.is_none() && marker.push(letter) {
+                *found = Some(position as u64);
+            }
+        }
+        if let [Some(first), Some(second)] = found {
+            return Ok((first, second));
+        }
     }
-    index
+
+    let missing = match found[0] {
+        Some(_) => &markers[1],
+        None => &markers[0],
+    };
+    Err(missing.missing(position).into())
+}
+
+pub fn solve1(datastream: &str) -> Result<i32, ParseError> {
+    find_unique_index(datastream, 4)
+}
+
+pub fn solve2(datastream: &str) -> Result<i32, ParseError> {
+    find_unique_index(datastream, 14)
 }
 
-fn part1(input: &str) -> i32 {
-    find_unique_index(input, 4)
+pub fn part1(input: &str) -> Result<i32, ParseError> {
+    solve1(parse(input)?)
 }
 
-fn part2(input: &str) -> i32 {
-    find_unique_index(input, 14)
+pub fn part2(input: &str) -> Result<i32, ParseError> {
+    solve2(parse(input)?)
 }
 
 #[test]
 fn test1() {
     for input in TEST_INPUTS {
-        let result = part1(input.0);
+        let result = part1(input.0).unwrap();
         assert_eq!(result, input.1);
     }
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 1582);
 }
 
 #[test]
 fn test2() {
     for input in TEST_INPUTS {
-        let result = part2(input.0);
+        let result = part2(input.0).unwrap();
         assert_eq!(result, input.2);
     }
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 3588);
+
+    assert_eq!(part2("abcabc").unwrap_err().length, 6);
+    assert_eq!(part2("abc1").unwrap_err().column, 4);
+}
+
+#[test]
+fn test_stream() {
+    for input in TEST_INPUTS {
+        let result = stream(format!("{}\n", input.0).as_bytes()).unwrap();
+        assert_eq!(result, (input.1 as u64, input.2 as u64));
+    }
+
+    let result = stream(get_input(DAY, false).as_bytes()).unwrap();
+    assert_eq!(result, (1582, 3588));
+
+    let Err(StreamError::Parse(error)) = stream("abcdabc".as_bytes()) else {
+        panic!("Expected a parse error.");
+    };
+    assert_eq!((error.column, error.length), (1, 7));
+    let Err(StreamError::Parse(error)) = stream("abc1".as_bytes()) else {
+        panic!("Expected a parse error.");
+    };
+    assert_eq!(error.column, 4);
+}
+
+/// Generates a datastream of a few letters, followed by a marker of 14 different letters.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = String> {
+    let letter = prop::char::range('a', 'f');
+    let marker = Just(('a'..='z').collect::<Vec<char>>()).prop_shuffle();
+    (prop::collection::vec(letter, 0..100), marker).prop_map(|(prefix, marker)| {
+        prefix
+            .into_iter()
+            .chain(marker.into_iter().take(14))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_first_marker(input in input_strategy()) {
+        let letters: Vec<char> = input.chars().collect();
+        for (part, size) in [(part1 as fn(&str) -> Result<i32, ParseError>, 4), (part2, 14)] {
+            // The first window of different letters, by brute force.
+            let expected = letters
+                .windows(size)
+                .position(|window| window.iter().collect::<std::collections::HashSet<_>>().len() == size)
+                .unwrap()
+                + size;
+            prop_assert_eq!(part(&input).unwrap(), expected as i32);
+        }
+        let (first, second) = stream(input.as_bytes()).unwrap();
+        prop_assert_eq!((first as i32, second as i32), (part1(&input).unwrap(), part2(&input).unwrap()));
+    }
 }
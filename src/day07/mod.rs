@@ -1,122 +1,108 @@
 // https://adventofcode.com/2022/day/7
 
 use crate::get_input;
-use std::i32;
-use std::str::FromStr;
+use crate::parse::{parse_lines_with, scan, ParseError};
+#[cfg(test)]
+use proptest::prelude::*;
+use std::error::Error;
 
 const DAY: u8 = 7;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-fn get_files_size(buffer: &Vec<&str>) -> i32 {
-    let mut size = 0;
-    for line in buffer {
-        let mut split = line.split(' ');
-        let dir_or_size = split.next().unwrap();
-
-        size += i32::from_str(dir_or_size).unwrap_or(0)
-    }
-    size
+enum Line<'a> {
+    ChangeDirectory(&'a str),
+    List,
+    Directory,
+    File(u32),
 }
 
-fn part1(input: &str) -> i32 {
-    const MAX_SIZE: i32 = 100000;
-
-    let mut result = 0;
-    let mut buffer: Vec<&str> = vec![];
-    let mut sizes: Vec<i32> = vec![];
-
-    for line in input.lines() {
-        if line.starts_with('$') {
-            if !buffer.is_empty() {
-                sizes.push(get_files_size(&buffer));
-                buffer.clear();
-            }
-            if line.starts_with("$ cd") {
-                let file_name = line[5..].to_string();
-                if file_name == ".." {
-                    let current_size = sizes.pop().unwrap();
-                    if current_size <= MAX_SIZE {
-                        result += current_size;
-                    }
-                    let previous_size = sizes.pop().unwrap() + current_size;
-                    sizes.push(previous_size);
-                }
-            }
-        } else {
-            buffer.push(line);
-        }
-    }
-
-    if !buffer.is_empty() {
-        sizes.push(get_files_size(&buffer));
-        buffer.clear();
-    }
-    while !sizes.is_empty() {
-        let current_size = sizes.pop().unwrap();
-        if current_size <= MAX_SIZE {
-            result += current_size;
+fn parse_line(line: &str) -> Result<Line<'_>, ParseError> {
+    if let Some(command) = line.strip_prefix("$ ") {
+        if command == "ls" {
+            return Ok(Line::List);
         }
-
-        if !sizes.is_empty() {
-            let previous_size = sizes.pop().unwrap() + current_size;
-            sizes.push(previous_size);
+        if let Some(directory) = command.strip_prefix("cd ") {
+            return Ok(Line::ChangeDirectory(directory));
         }
+        return Err(ParseError::new(3, "expected `cd DIRECTORY` or `ls`")
+            .with_length(command.chars().count()));
+    }
+    if line.starts_with("dir ") {
+        return Ok(Line::Directory);
     }
 
-    result
+    let (size, _) = scan!(line, "{} {}", u32, String)?;
+    Ok(Line::File(size))
 }
 
-fn part2(input: &str) -> i32 {
-    const TOTAL_SPACE: i32 = 70000000;
-    const REQUIRED_SPACE: i32 = 30000000;
-
-    let mut buffer: Vec<&str> = vec![];
-    let mut sizes: Vec<i32> = vec![];
-    let mut all_sizes: Vec<i32> = vec![];
-
-    for line in input.lines() {
-        if line.starts_with('$') {
-            if !buffer.is_empty() {
-                sizes.push(get_files_size(&buffer));
-                buffer.clear();
-            }
-            if line.starts_with("$ cd") {
-                let file_name = line[5..].to_string();
-                if file_name == ".." {
-                    let current_size = sizes.pop().unwrap();
-                    all_sizes.push(current_size);
-
-                    let previous_size = sizes.pop().unwrap() + current_size;
-                    sizes.push(previous_size);
+/// Returns the total size of every directory, in the order they are left. The sizes are added in
+/// `i64`, so they cannot overflow.
+pub fn parse(input: &str) -> Result<Vec<i64>, ParseError> {
+    let lines = parse_lines_with(input, parse_line)?;
+
+    // The sizes of the directories from the root to the current one.
+    let mut sizes: Vec<i64> = vec![];
+    let mut all_sizes: Vec<i64> = vec![];
+
+    for (i, (line, text)) in lines.into_iter().zip(input.lines()).enumerate() {
+        match line {
+            Line::ChangeDirectory("..") => {
+                if sizes.len() < 2 {
+                    return Err(ParseError::whole_line(
+                        i + 1,
+                        text,
+                        "expected a directory to leave",
+                    ));
                 }
+                let current_size = sizes.pop().unwrap();
+                all_sizes.push(current_size);
+                *sizes.last_mut().unwrap() += current_size;
             }
-        } else {
-            buffer.push(line);
+            Line::ChangeDirectory(_) => sizes.push(0),
+            Line::File(size) => match sizes.last_mut() {
+                Some(current_size) => *current_size += i64::from(size),
+                None => {
+                    return Err(ParseError::whole_line(
+                        i + 1,
+                        text,
+                        "expected a `cd` before the first file",
+                    ))
+                }
+            },
+            Line::List | Line::Directory => {}
         }
     }
 
-    if !buffer.is_empty() {
-        sizes.push(get_files_size(&buffer));
-        buffer.clear();
-    }
-    while !sizes.is_empty() {
-        let current_size = sizes.pop().unwrap();
+    while let Some(current_size) = sizes.pop() {
         all_sizes.push(current_size);
-
-        if !sizes.is_empty() {
-            let previous_size = sizes.pop().unwrap() + current_size;
-            sizes.push(previous_size);
+        if let Some(previous_size) = sizes.last_mut() {
+            *previous_size += current_size;
         }
     }
 
+    Ok(all_sizes)
+}
+
+pub fn solve1(sizes: &[i64]) -> i64 {
+    const MAX_SIZE: i64 = 100000;
+
+    sizes.iter().filter(|&&size| size <= MAX_SIZE).sum()
+}
+
+pub fn solve2(sizes: &[i64]) -> i64 {
+    const TOTAL_SPACE: i64 = 70000000;
+    const REQUIRED_SPACE: i64 = 30000000;
+
+    let mut all_sizes = sizes.to_vec();
     all_sizes.sort();
-    let free_space = TOTAL_SPACE - all_sizes.last().unwrap();
+    let free_space = TOTAL_SPACE - all_sizes.last().unwrap_or(&0);
     for size in all_sizes {
         if free_space + size >= REQUIRED_SPACE {
             return size;
@@ -126,20 +112,116 @@ fn part2(input: &str) -> i32 {
     -1
 }
 
+pub fn part1(input: &str) -> Result<i64, ParseError> {
+    Ok(solve1(&parse(input)?))
+}
+
+pub fn part2(input: &str) -> Result<i64, ParseError> {
+    Ok(solve2(&parse(input)?))
+}
+
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 95437);
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 1513699);
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 24933642);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 7991939);
+
+    let error = part2("$ cd /\n$ ls\n12x a.txt").unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (3, 1, 3));
+    let error = part2("$ cd /\n$ cd ..").unwrap_err();
+    assert_eq!(error.line, 2);
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct Directory {
+    files: Vec<u32>,
+    directories: Vec<Directory>,
+}
+
+#[cfg(test)]
+impl Directory {
+    /// Writes the commands listing this directory and then exploring its subdirectories.
+    fn transcript(&self, lines: &mut Vec<String>) {
+        lines.push("$ ls".to_string());
+        for (i, size) in self.files.iter().enumerate() {
+            lines.push(format!("{size} file{i}.txt"));
+        }
+        for i in 0..self.directories.len() {
+            lines.push(format!("dir d{i}"));
+        }
+        for (i, directory) in self.directories.iter().enumerate() {
+            lines.push(format!("$ cd d{i}"));
+            directory.transcript(lines);
+            lines.push("$ cd ..".to_string());
+        }
+    }
+
+    /// Returns the total size of this directory, after the sizes of all its subdirectories.
+    fn sizes(&self, sizes: &mut Vec<i64>) -> i64 {
+        let mut total: i64 = self.files.iter().map(|&size| i64::from(size)).sum();
+        for directory in &self.directories {
+            total += directory.sizes(sizes);
+        }
+        sizes.push(total);
+        total
+    }
+}
+
+/// Generates a file system and the transcript of its exploration.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Directory, String)> {
+    let files = || prop::collection::vec(1..200000_u32, 0..4);
+    let leaf = files().prop_map(|files| Directory {
+        files,
+        directories: vec![],
+    });
+    let tree = leaf.prop_recursive(4, 40, 4, move |inner| {
+        (files(), prop::collection::vec(inner, 0..4))
+            .prop_map(|(files, directories)| Directory { files, directories })
+    });
+    tree.prop_map(|root| {
+        let mut lines = vec!["$ cd /".to_string()];
+        root.transcript(&mut lines);
+        (root, lines.join("\n"))
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_directory_sizes((root, input) in input_strategy()) {
+        let mut sizes = vec![];
+        let total = root.sizes(&mut sizes);
+
+        let mut result = parse(&input).unwrap();
+        prop_assert_eq!(result.iter().max(), Some(&total));
+        result.sort();
+        sizes.sort();
+        prop_assert_eq!(&result, &sizes);
+
+        let small: i64 = sizes.iter().filter(|&&size| size <= 100000).sum();
+        prop_assert_eq!(part1(&input).unwrap(), small);
+
+        // The deleted directory frees enough space, and no smaller one would.
+        let deleted = part2(&input).unwrap();
+        let needed = 30000000 - (70000000 - total);
+        if needed <= 0 {
+            prop_assert_eq!(deleted, sizes[0]);
+        } else {
+            prop_assert!(deleted >= needed);
+            prop_assert!(sizes.iter().all(|&size| size >= deleted || size < needed));
+        }
+    }
 }
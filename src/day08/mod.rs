@@ -1,27 +1,59 @@
 // https://adventofcode.com/2022/day/8
 
 use crate::get_input;
+use crate::parse::ParseError;
+#[cfg(test)]
+use proptest::prelude::*;
 use std::cmp::max;
-use std::i32;
+use std::error::Error;
 
 const DAY: u8 = 8;
 const MAX_SIZE: usize = 100;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-fn part1(input: &str) -> i32 {
-    let size = input.lines().next().unwrap().len();
+/// Returns the rows of trees, checking that they form a square of digits.
+pub fn parse(input: &str) -> Result<Vec<&str>, ParseError> {
+    let size = input.lines().count();
+    if size > MAX_SIZE {
+        return Err(
+            ParseError::new(1, format!("expected at most {MAX_SIZE} rows")).on_line(MAX_SIZE + 1),
+        );
+    }
+
+    for (i, line) in input.lines().enumerate() {
+        if let Some(column) = line.find(|c: char| !c.is_ascii_digit()) {
+            let found = line[column..].chars().next().unwrap();
+            return Err(
+                ParseError::new(column + 1, format!("expected a digit, found `{found}`"))
+                    .in_line(i + 1, line),
+            );
+        }
+        if line.len() != size {
+            return Err(ParseError::whole_line(
+                i + 1,
+                line,
+                format!("expected {size} trees, as many as there are rows"),
+            ));
+        }
+    }
+    Ok(input.lines().collect())
+}
+
+pub fn solve1(rows: &[&str]) -> i32 {
+    let size = rows.len();
 
     let mut visible_trees = [[false; MAX_SIZE]; MAX_SIZE];
     let mut tallest_top = [' '; MAX_SIZE];
     let mut tallest_left = [' '; MAX_SIZE];
 
-    for (i, line) in input.lines().enumerate() {
+    for (i, line) in rows.iter().enumerate() {
         for (j, char) in line.chars().enumerate() {
             if char > tallest_top[j] {
                 tallest_top[j] = char;
@@ -38,7 +70,7 @@ fn part1(input: &str) -> i32 {
     let mut tallest_bottom = [' '; MAX_SIZE];
     let mut tallest_right = [' '; MAX_SIZE];
 
-    for (i, line) in input.lines().rev().enumerate() {
+    for (i, line) in rows.iter().rev().enumerate() {
         for (j, char) in line.chars().rev().enumerate() {
             if char > tallest_bottom[j] {
                 tallest_bottom[j] = char;
@@ -58,8 +90,8 @@ fn part1(input: &str) -> i32 {
     result
 }
 
-fn part2(input: &str) -> i32 {
-    let size = input.lines().next().unwrap().len();
+pub fn solve2(rows: &[&str]) -> i32 {
+    let size = rows.len();
 
     let mut trees = [[' '; MAX_SIZE]; MAX_SIZE];
 
@@ -68,7 +100,7 @@ fn part2(input: &str) -> i32 {
     let mut visible_right = [[0; MAX_SIZE]; MAX_SIZE];
     let mut visible_bottom = [[0; MAX_SIZE]; MAX_SIZE];
 
-    for (i, line) in input.lines().enumerate() {
+    for (i, line) in rows.iter().enumerate() {
         for (j, char) in line.chars().enumerate() {
             trees[i][j] = char;
             for (number_of_trees, k) in (0..j).rev().enumerate() {
@@ -138,20 +170,94 @@ fn part2(input: &str) -> i32 {
     best as i32
 }
 
+pub fn part1(input: &str) -> Result<i32, ParseError> {
+    Ok(solve1(&parse(input)?))
+}
+
+pub fn part2(input: &str) -> Result<i32, ParseError> {
+    Ok(solve2(&parse(input)?))
+}
+
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 21);
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 1733);
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 8);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 284648);
+
+    let error = part2("123\n4x6\n789").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 2));
+    let error = part2("123\n45\n789").unwrap_err();
+    assert_eq!((error.line, error.length), (2, 2));
+}
+
+/// Generates a square of trees and the map showing their heights.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<Vec<u8>>, String)> {
+    (1..15_usize)
+        .prop_flat_map(|size| prop::collection::vec(prop::collection::vec(0..10_u8, size), size))
+        .prop_map(|trees| {
+            let lines: Vec<String> = trees
+                .iter()
+                .map(|row| row.iter().map(|height| height.to_string()).collect())
+                .collect();
+            (trees, lines.join("\n"))
+        })
+}
+
+/// Returns the number of trees seen in each direction from a tree, and whether the edge is seen.
+#[cfg(test)]
+fn views(trees: &[Vec<u8>], row: usize, column: usize) -> Vec<(usize, bool)> {
+    let size = trees.len() as i32;
+    let height = trees[row][column];
+    let mut views = vec![];
+    for (dy, dx) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+        let (mut y, mut x) = (row as i32 + dy, column as i32 + dx);
+        let mut seen = 0;
+        let mut edge = true;
+        while (0..size).contains(&y) && (0..size).contains(&x) {
+            seen += 1;
+            if trees[y as usize][x as usize] >= height {
+                edge = false;
+                break;
+            }
+            y += dy;
+            x += dx;
+        }
+        views.push((seen, edge));
+    }
+    views
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_visibility((trees, input) in input_strategy()) {
+        let size = trees.len();
+        let mut visible = 0;
+        let mut best = 0;
+        for row in 0..size {
+            for column in 0..size {
+                let views = views(&trees, row, column);
+                visible += views.iter().any(|&(_, edge)| edge) as i32;
+                best = best.max(views.iter().map(|&(seen, _)| seen).product::<usize>());
+            }
+        }
+
+        let result = part1(&input).unwrap();
+        prop_assert_eq!(result, visible);
+        // The trees on the edges are always visible.
+        prop_assert!(result as usize >= (4 * size).saturating_sub(4).max(1));
+        prop_assert_eq!(part2(&input).unwrap(), best as i32);
+    }
 }
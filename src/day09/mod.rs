@@ -1,19 +1,48 @@
 // https://adventofcode.com/2022/day/9
 
 use std::collections::HashSet;
-use std::i32;
-use std::str::FromStr;
+use std::error::Error;
+use std::io::{self, BufRead};
 
 use crate::get_input;
+use crate::grid::Grid;
+use crate::parse::{parse_field, parse_lines_with, scan_fields, ParseError};
+use crate::render::{self, renderer, Color};
+use crate::simulation::{run_rendered, Frames, Render, Simulation};
+use crate::stream::{for_each_line, StreamError};
+use crate::terminal;
+use crate::trace;
+#[cfg(test)]
+use proptest::prelude::*;
 
 const DAY: u8 = 9;
 const MAX_NODES: usize = 10;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
+}
+
+/// The direction and the number of steps of a motion of the head.
+pub type Motion<'a> = (&'a str, usize);
+
+/// Returns the direction and the number of steps of a motion.
+fn parse_motion(line: &str) -> Result<Motion<'_>, ParseError> {
+    let fields = scan_fields(line, "{} {}")?;
+    let (_, direction) = fields[0];
+    if !["U", "D", "L", "R"].contains(&direction) {
+        return Err(ParseError::new(1, "expected `U`, `D`, `L` or `R`")
+            .with_length(direction.chars().count()));
+    }
+    let steps = parse_field(Some(fields[1]))?;
+    Ok((direction, steps))
+}
+
+pub fn parse(input: &str) -> Result<Vec<Motion<'_>>, ParseError> {
+    parse_lines_with(input, parse_motion)
 }
 
 fn move_node(direction: &str, node: &mut [i32; 2]) {
@@ -50,54 +79,303 @@ fn normalize_node(parent_node: [i32; 2], node: &mut [i32; 2]) {
     }
 }
 
-fn solve(input: &str, nodes_number: usize) -> usize {
-    let mut visited: HashSet<[i32; 2]> = HashSet::new();
-    let mut nodes: [[i32; 2]; MAX_NODES] = [[0, 0]; MAX_NODES];
-    visited.insert([0, 0]);
+/// Moves the head one step, and the other knots after it.
+fn step_knots(direction: &str, nodes: &mut [[i32; 2]]) {
+    move_node(direction, &mut nodes[0]);
+    for current_node in 1..nodes.len() {
+        normalize_node(nodes[current_node - 1], &mut nodes[current_node]);
+    }
+}
 
-    for line in input.lines() {
-        let direction = &line[..1];
-        let steps = i32::from_str(&line[2..]).unwrap();
+fn rope_color(cell: &char) -> Color {
+    match cell {
+        'H' => [230, 60, 60],
+        'o' => [230, 190, 90],
+        '#' => [60, 90, 160],
+        _ => [20, 20, 30],
+    }
+}
 
-        for _ in 0..steps {
-            move_node(direction, &mut nodes[0]);
-            for current_node in 1..nodes_number {
-                normalize_node(nodes[current_node - 1], &mut nodes[current_node]);
-            }
-            visited.insert(nodes[nodes_number - 1]);
+/// The lowest and the highest coordinates of a rope. The knots never leave the area visited by
+/// the head.
+type Bounds = ([i32; 2], [i32; 2]);
+
+fn head_bounds(heads: impl Iterator<Item = [i32; 2]>) -> Bounds {
+    let (mut lower, mut upper) = ([0, 0], [0, 0]);
+    for head in heads {
+        lower = [lower[0].min(head[0]), lower[1].min(head[1])];
+        upper = [upper[0].max(head[0]), upper[1].max(head[1])];
+    }
+    (lower, upper)
+}
+
+fn input_bounds(motions: &[Motion]) -> Bounds {
+    let mut head = [0, 0];
+    let directions = motions
+        .iter()
+        .flat_map(|&(direction, steps)| std::iter::repeat_n(direction, steps));
+    head_bounds(directions.map(|direction| {
+        move_node(direction, &mut head);
+        head
+    }))
+}
+
+fn grid_size((lower, upper): Bounds) -> (usize, usize) {
+    (
+        (upper[0] - lower[0] + 1) as usize,
+        (upper[1] - lower[1] + 1) as usize,
+    )
+}
+
+/// Returns the position of the node in the grid. The y axis points up in the puzzle and down in
+/// the grid.
+fn grid_cell(node: [i32; 2], (lower, upper): Bounds) -> (usize, usize) {
+    ((node[0] - lower[0]) as usize, (upper[1] - node[1]) as usize)
+}
+
+fn draw_rope(grid: &mut Grid<char>, nodes: &[[i32; 2]], bounds: Bounds) {
+    for (i, node) in nodes.iter().enumerate().rev() {
+        let (x, y) = grid_cell(*node, bounds);
+        grid.set(x, y, if i == 0 { 'H' } else { 'o' });
+    }
+}
+
+fn visited_grid(visited: &HashSet<[i32; 2]>, bounds: Bounds) -> Grid<char> {
+    let (width, height) = grid_size(bounds);
+    let (lower, upper) = bounds;
+    Grid::from_fn(width, height, |x, y| {
+        if visited.contains(&[lower[0] + x as i32, upper[1] - y as i32]) {
+            return '#';
         }
+        '.'
+    })
+}
+
+/// A rope whose head follows the moves one step at a time.
+#[derive(Clone)]
+struct Rope<'a> {
+    motions: &'a [Motion<'a>],
+    /// The current motion, and the number of its steps already taken.
+    motion: usize,
+    taken: usize,
+    nodes: [[i32; 2]; MAX_NODES],
+    nodes_number: usize,
+    visited: HashSet<[i32; 2]>,
+    /// The visited positions drawn on a grid, kept up to date when the rope is drawn.
+    visited_grid: Option<Grid<char>>,
+    bounds: Bounds,
+}
+
+impl<'a> Rope<'a> {
+    fn new(motions: &'a [Motion<'a>], nodes_number: usize) -> Self {
+        let mut rope = Rope {
+            motions,
+            motion: 0,
+            taken: 0,
+            nodes: [[0, 0]; MAX_NODES],
+            nodes_number,
+            visited: HashSet::from([[0, 0]]),
+            visited_grid: None,
+            bounds: ([0, 0], [0, 0]),
+        };
+        rope.skip_finished_motions();
+        rope
+    }
+
+    /// Moves on to the first motion with steps left, if the current one has none.
+    fn skip_finished_motions(&mut self) {
+        while self
+            .motions
+            .get(self.motion)
+            .is_some_and(|&(_, steps)| self.taken == steps)
+        {
+            self.motion += 1;
+            self.taken = 0;
+        }
+    }
+}
+
+impl Simulation for Rope<'_> {
+    fn step(&mut self) {
+        let (direction, _) = self.motions[self.motion];
+        step_knots(direction, &mut self.nodes[..self.nodes_number]);
+        let tail = self.nodes[self.nodes_number - 1];
+        self.visited.insert(tail);
+        if let Some(grid) = &mut self.visited_grid {
+            let (x, y) = grid_cell(tail, self.bounds);
+            grid.set(x, y, '#');
+        }
+        self.taken += 1;
+        self.skip_finished_motions();
+    }
+
+    fn is_done(&self) -> bool {
+        self.motion == self.motions.len()
     }
+}
+
+impl Render for Rope<'_> {
+    type Cell = char;
+
+    fn grid(&self) -> Grid<char> {
+        let mut grid = match &self.visited_grid {
+            Some(grid) => grid.clone(),
+            None => visited_grid(&self.visited, self.bounds),
+        };
+        draw_rope(&mut grid, &self.nodes[..self.nodes_number], self.bounds);
+        grid
+    }
+
+    fn focus(&self) -> (usize, usize) {
+        grid_cell(self.nodes[0], self.bounds)
+    }
+}
+
+fn solve(motions: &[Motion], nodes_number: usize) -> io::Result<usize> {
+    let mut rope = Rope::new(motions, nodes_number);
+
+    let name = format!("day09_{nodes_number}_knots");
+    let title = format!("Day 9, {nodes_number} knots");
+    let bounds = match render::is_enabled() || terminal::is_enabled() || trace::is_enabled() {
+        true => input_bounds(motions),
+        false => ([0, 0], [0, 0]),
+    };
+    let frames = Frames::new(
+        renderer(&name, grid_size(bounds), rope_color, 2, 10)?,
+        terminal::animation(&title, |&cell| cell, rope_color),
+        trace::recorder(&name, |&cell| cell, rope_color),
+    );
+    if frames.is_active() {
+        rope.bounds = bounds;
+        rope.visited_grid = Some(visited_grid(&rope.visited, bounds));
+    }
+
+    run_rendered(&mut rope, frames, Rope::grid);
+
+    Ok(rope.visited.len())
+}
+
+pub fn solve1(motions: &[Motion]) -> io::Result<usize> {
+    solve(motions, 2)
+}
+
+pub fn solve2(motions: &[Motion]) -> io::Result<usize> {
+    solve(motions, 10)
+}
+
+/// Returns the answers of both parts, reading the motions one line at a time and moving both
+/// ropes together. Only the visited positions are kept in memory.
+pub fn stream(reader: impl BufRead) -> Result<(usize, usize), StreamError> {
+    let mut short_rope = [[0, 0]; 2];
+    let mut long_rope = [[0, 0]; MAX_NODES];
+    let mut short_visited = HashSet::from([[0, 0]]);
+    let mut long_visited = HashSet::from([[0, 0]]);
+
+    for_each_line(reader, |number, line| {
+        let (direction, steps) = parse_motion(line).map_err(|error| error.in_line(number, line))?;
+        for _ in 0..steps {
+            step_knots(direction, &mut short_rope);
+            short_visited.insert(short_rope[1]);
+            step_knots(direction, &mut long_rope);
+            long_visited.insert(long_rope[MAX_NODES - 1]);
+        }
+        Ok(())
+    })?;
 
-    visited.len()
+    Ok((short_visited.len(), long_visited.len()))
 }
 
-fn part1(input: &str) -> usize {
-    solve(input, 2)
+pub fn part1(input: &str) -> Result<usize, Box<dyn Error>> {
+    Ok(solve1(&parse(input)?)?)
 }
 
-fn part2(input: &str) -> usize {
-    solve(input, 10)
+pub fn part2(input: &str) -> Result<usize, Box<dyn Error>> {
+    Ok(solve2(&parse(input)?)?)
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 13);
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 6367);
+
+    // Motions can be as long as needed, or have no steps at all.
+    let result = part1("R 0\nR 5000\nU 0").unwrap();
+    assert_eq!(result, 5000);
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 1);
 
     let test_input_2 = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
 
-    let result = part2(test_input_2);
+    let result = part2(test_input_2).unwrap();
     assert_eq!(result, 36);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 2536);
+
+    let error = parse("R 5\nX 8").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 1));
+    let error = parse("R 5\nU -8").unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (2, 3, 2));
+}
+
+#[test]
+fn test_stream() {
+    let result = stream(get_input(DAY, true).as_bytes()).unwrap();
+    assert_eq!(result, (13, 1));
+
+    let result = stream(get_input(DAY, false).as_bytes()).unwrap();
+    assert_eq!(result, (6367, 2536));
+
+    let Err(StreamError::Parse(error)) = stream("R 5\r\nX 8\r\n".as_bytes()) else {
+        panic!("Expected a parse error.");
+    };
+    assert_eq!((error.line, error.column), (2, 1));
+}
+
+/// Generates the motions of the head.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = String> {
+    let motion = (prop::sample::select(vec!["U", "D", "L", "R"]), 1..10_usize);
+    prop::collection::vec(motion, 0..40).prop_map(|motions| {
+        let lines: Vec<String> = motions
+            .iter()
+            .map(|(direction, steps)| format!("{direction} {steps}"))
+            .collect();
+        lines.join("\n")
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_knots_stay_adjacent(input in input_strategy(), nodes_number in 2..=MAX_NODES) {
+        let motions = parse(&input).unwrap();
+        let mut rope = Rope::new(&motions, nodes_number);
+        while !rope.is_done() {
+            rope.step();
+            for knots in rope.nodes[..nodes_number].windows(2) {
+                let [head, tail] = knots else { unreachable!() };
+                prop_assert!(head[0].abs_diff(tail[0]) <= 1 && head[1].abs_diff(tail[1]) <= 1);
+            }
+        }
+
+        // The tail visits at most one new position per step.
+        prop_assert!(rope.visited.contains(&[0, 0]));
+        let steps: usize = motions.iter().map(|&(_, steps)| steps).sum();
+        prop_assert!(rope.visited.len() <= steps + 1);
+    }
+
+    #[test]
+    fn property_longer_ropes_visit_less(input in input_strategy()) {
+        let (short, long) = (part1(&input).unwrap(), part2(&input).unwrap());
+        prop_assert!(long <= short);
+        prop_assert_eq!(stream(input.as_bytes()).unwrap(), (short, long));
+    }
 }
@@ -1,73 +1,167 @@
 // https://adventofcode.com/2022/day/10
 
-use std::i32;
-use std::str::FromStr;
+use std::error::Error;
+use std::io;
 
 use crate::get_input;
+use crate::grid::Grid;
+use crate::ocr::recognize;
+use crate::parse::{parse_lines_with, scan, ParseError};
+use crate::render::{renderer, Color};
+use crate::simulation::{run, run_rendered, Frames, Render, Simulation};
+use crate::terminal;
+use crate::trace;
+#[cfg(test)]
+use proptest::prelude::*;
 
 const DAY: u8 = 10;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    part2(&get_input(DAY, true), true);
-    part2(&get_input(DAY, false), true);
-}
-
-fn part1(input: &str) -> i32 {
-    let mut result = 0;
-    let mut register = 1;
-    let mut cycle = 1;
-    let mut next_cycle_check = 20;
-
-    for line in input.lines() {
-        let current_cycle_register = register;
-        if line == "noop" {
-            cycle += 1;
-        } else {
-            let value = i32::from_str(&line[5..]).unwrap();
-            cycle += 2;
-            register += value;
-        }
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    draw_screen(&parse(&get_input(DAY, true))?, true)?;
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
+}
 
-        if cycle == next_cycle_check {
-            result += cycle * register;
-            next_cycle_check += 40;
-        } else if cycle - 1 == next_cycle_check {
-            result += (cycle - 1) * current_cycle_register;
-            next_cycle_check += 40;
-        }
+/// Returns the value added by an instruction, `None` for `noop`.
+fn parse_instruction(line: &str) -> Result<Option<i32>, ParseError> {
+    if line == "noop" {
+        return Ok(None);
+    }
+    if !line.starts_with("addx ") {
+        return Err(
+            ParseError::new(1, "expected `noop` or `addx VALUE`").with_length(line.chars().count())
+        );
     }
+    let (value,) = scan!(line, "addx {}", i32)?;
+    Ok(Some(value))
+}
+
+/// Returns the values added by the instructions, `None` for `noop`.
+pub fn parse(input: &str) -> Result<Vec<Option<i32>>, ParseError> {
+    parse_lines_with(input, parse_instruction)
+}
 
-    result
+pub fn solve1(instructions: &[Option<i32>]) -> i64 {
+    let mut cpu = Cpu::new(instructions);
+    run(&mut cpu);
+    cpu.signal_strength
 }
 
-fn should_draw(cycle: i32, register: i32) -> bool {
+pub fn part1(input: &str) -> Result<i64, ParseError> {
+    Ok(solve1(&parse(input)?))
+}
+
+fn should_draw(cycle: i64, register: i64) -> bool {
     let sprite_distance = cycle % 40 - register;
     (0..3).contains(&sprite_distance)
 }
 
-fn part2(input: &str, print_result: bool) -> [[bool; 40]; 6] {
-    let mut register = 0_i32;
-    let mut cycle = 0_usize;
-    let mut screen = [[false; 40]; 6];
+fn screen_color(cell: &u8) -> Color {
+    match cell {
+        2 => [230, 60, 60],
+        1 => [90, 230, 90],
+        _ => [20, 20, 30],
+    }
+}
 
-    for line in input.lines() {
-        screen[cycle / 40][cycle % 40] = should_draw(cycle as i32, register);
+fn screen_grid(screen: &[[bool; 40]; 6], beam: usize) -> Grid<u8> {
+    Grid::from_fn(40, 6, |x, y| {
+        if y * 40 + x == beam {
+            return 2;
+        }
+        screen[y][x] as u8
+    })
+}
 
-        if line == "noop" {
-            cycle += 1;
-        } else {
-            let value = i32::from_str(&line[5..]).unwrap();
-            cycle += 1;
-            screen[cycle / 40][cycle % 40] = should_draw(cycle as i32, register);
-            cycle += 1;
-            register += value;
+fn screen_symbol(cell: &u8) -> char {
+    match cell {
+        2 => '@',
+        1 => '#',
+        _ => '.',
+    }
+}
+
+/// The CPU, advanced one cycle at a time, and the screen it draws.
+#[derive(Clone)]
+struct Cpu {
+    /// The values added by the instructions, `None` for `noop`.
+    instructions: Vec<Option<i32>>,
+    next: usize,
+    /// Whether the first cycle of the current `addx` is done.
+    adding: bool,
+    /// The program stops after 240 cycles, so the register cannot overflow when it is kept in
+    /// `i64`.
+    register: i64,
+    cycle: usize,
+    signal_strength: i64,
+    screen: [[bool; 40]; 6],
+}
+
+impl Cpu {
+    fn new(instructions: &[Option<i32>]) -> Self {
+        Cpu {
+            instructions: instructions.to_vec(),
+            next: 0,
+            adding: false,
+            register: 1,
+            cycle: 0,
+            signal_strength: 0,
+            screen: [[false; 40]; 6],
+        }
+    }
+}
+
+impl Simulation for Cpu {
+    fn step(&mut self) {
+        // The register keeps its value during the whole cycle.
+        let cycle = self.cycle + 1;
+        if cycle % 40 == 20 {
+            self.signal_strength += cycle as i64 * self.register;
+        }
+        self.screen[self.cycle / 40][self.cycle % 40] =
+            should_draw(self.cycle as i64, self.register - 1);
+
+        // After the end of the program, the CPU idles until the screen is drawn.
+        match self.instructions.get(self.next) {
+            Some(Some(value)) if self.adding => {
+                self.register += i64::from(*value);
+                self.adding = false;
+                self.next += 1;
+            }
+            Some(Some(_)) => self.adding = true,
+            _ => self.next += 1,
         }
+        self.cycle = cycle;
     }
-    if cycle / 40 < 6 {
-        screen[cycle / 40][cycle % 40] = should_draw(cycle as i32, register);
+
+    fn is_done(&self) -> bool {
+        self.cycle == 240
     }
+}
+
+impl Render for Cpu {
+    type Cell = u8;
+
+    fn grid(&self) -> Grid<u8> {
+        screen_grid(&self.screen, self.cycle)
+    }
+
+    fn focus(&self) -> (usize, usize) {
+        (self.cycle % 40, self.cycle / 40)
+    }
+}
+
+fn draw_screen(instructions: &[Option<i32>], print_result: bool) -> io::Result<[[bool; 40]; 6]> {
+    let mut cpu = Cpu::new(instructions);
+    let frames = Frames::new(
+        renderer("day10", (40, 6), screen_color, 8, 1)?,
+        terminal::animation("Day 10", screen_symbol, screen_color),
+        trace::recorder("day10", screen_symbol, screen_color),
+    );
+    run_rendered(&mut cpu, frames, |cpu| screen_grid(&cpu.screen, usize::MAX));
+    let screen = cpu.screen;
 
     if print_result {
         for line in screen {
@@ -82,16 +176,29 @@ fn part2(input: &str, print_result: bool) -> [[bool; 40]; 6] {
         }
     }
 
-    screen
+    Ok(screen)
+}
+
+pub fn solve2(instructions: &[Option<i32>]) -> Result<String, Box<dyn Error>> {
+    Ok(recognize(&draw_screen(instructions, false)?)?)
+}
+
+pub fn part2(input: &str) -> Result<String, Box<dyn Error>> {
+    solve2(&parse(input)?)
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 13140);
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 13820);
+
+    let error = part1("noop\naddx\naddx 3").unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (2, 1, 4));
+    let error = part1("noop\naddx three").unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (2, 6, 5));
 }
 
 #[test]
@@ -109,7 +216,7 @@ fn test2() {
         }
     }
 
-    let result = part2(&get_input(DAY, true), false);
+    let result = draw_screen(&parse(&get_input(DAY, true)).unwrap(), false).unwrap();
     assert_eq!(result, test_result_2_bool);
 
     println!();
@@ -127,6 +234,51 @@ fn test2() {
         }
     }
 
-    let result = part2(&get_input(DAY, false), false);
+    let result = draw_screen(&parse(&get_input(DAY, false)).unwrap(), false).unwrap();
     assert_eq!(result, test_result_2_bool);
+
+    let result = part2(&get_input(DAY, false)).unwrap();
+    assert_eq!(result, "ZKGRKGRK");
+}
+
+/// Generates a program, `None` standing for `noop`, and its listing.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<Option<i32>>, String)> {
+    prop::collection::vec(prop::option::of(-20..20), 0..200).prop_map(|program| {
+        let lines: Vec<String> = program
+            .iter()
+            .map(|instruction| match instruction {
+                Some(value) => format!("addx {value}"),
+                None => "noop".to_string(),
+            })
+            .collect();
+        (program, lines.join("\n"))
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_register_values((program, input) in input_strategy()) {
+        // The value of the register during every cycle, by expanding the instructions.
+        let mut register = 1_i64;
+        let mut values = vec![];
+        for instruction in program {
+            values.push(register);
+            if let Some(value) = instruction {
+                values.push(register);
+                register += i64::from(value);
+            }
+        }
+        values.resize(values.len().max(240), register);
+
+        let strength: i64 = (20..=220).step_by(40).map(|cycle| cycle as i64 * values[cycle - 1]).sum();
+        prop_assert_eq!(part1(&input).unwrap(), strength);
+
+        let screen = draw_screen(&parse(&input).unwrap(), false).unwrap();
+        for (cycle, value) in values.iter().take(240).enumerate() {
+            let lit = (value - (cycle % 40) as i64).abs() <= 1;
+            prop_assert_eq!(screen[cycle / 40][cycle % 40], lit);
+        }
+    }
 }
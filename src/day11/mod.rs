@@ -1,180 +1,476 @@
 // https://adventofcode.com/2022/day/11
 
+use crate::big_uint::BigUint;
+use crate::checked::{self, Number, OverflowError};
 use crate::get_input;
-use regex::Regex;
+use crate::number_theory::lcm;
+use crate::parse::{blocks, parse_field, scan_fields, signed_integers, Block, ParseError};
+use crate::simulation::{run_steps, Simulation};
+#[cfg(test)]
+use proptest::prelude::*;
 use std::collections::VecDeque;
-use std::ops::{Add, Div, Mul, Sub};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
 
 const DAY: u8 = 11;
 const MAX_MONKEYS: usize = 8;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-fn gcd(a: i32, b: i32) -> i32 {
-    if b != 0 {
-        return gcd(b, a % b);
-    }
-    a
-}
-
-fn lcm(a: i32, b: i32) -> i32 {
-    a * b / gcd(a, b)
-}
-
-#[derive(Debug)]
-struct Monkey {
-    items: VecDeque<i32>,
+#[derive(Debug, Clone)]
+struct Monkey<W> {
+    items: VecDeque<W>,
     operation_operator: char,
-    operation_number: Option<i32>,
-    inspection_divider: i32,
-    divisible_by: i32,
-    divisible_target: i32,
-    indivisible_target: i32,
+    operation_number: Option<W>,
+    inspection_divider: W,
+    divisible_by: W,
+    divisible_target: usize,
+    indivisible_target: usize,
 }
 
-impl Monkey {
-    fn add_item(&mut self, value: i32) {
+impl<W: Number> Monkey<W> {
+    fn add_item(&mut self, value: W) {
         self.items.push_back(value);
     }
 
-    fn throw(&mut self, modulo: i32) -> (i32, i32) {
+    fn throw(&mut self, modulo: Option<&W>) -> Result<(W, usize), OverflowError> {
         let value = self.items.pop_front().unwrap();
-        let operation: fn(i64, i64) -> i64 = match self.operation_operator {
-            '+' => i64::add,
-            '-' => i64::sub,
-            '*' => i64::mul,
-            '/' => i64::div,
+        let operand = self.operation_number.clone().unwrap_or(value.clone());
+        let value = match self.operation_operator {
+            '+' => checked::add(value, operand)?,
+            '-' => checked::sub(value, operand)?,
+            '*' => checked::mul(value, operand)?,
+            '/' => checked::div(value, operand)?,
             _ => panic!("Unknown operator."),
         };
 
-        let value = operation(value as i64, self.operation_number.unwrap_or(value) as i64)
-            / self.inspection_divider as i64
-            % modulo as i64;
-        let value = value as i32;
-        if value % self.divisible_by == 0 {
-            return (value, self.divisible_target);
+        let mut value = checked::div(value, self.inspection_divider.clone())?;
+        if let Some(modulo) = modulo {
+            value = checked::rem(value, modulo.clone())?;
+        }
+        if checked::rem(value.clone(), self.divisible_by.clone())?.is_zero() {
+            return Ok((value, self.divisible_target));
         }
 
-        (value, self.indivisible_target)
+        Ok((value, self.indivisible_target))
     }
 }
 
-fn create_monkey(buffer: &[&str], inspection_divider: i32) -> Monkey {
-    let items = buffer[1]
-        .strip_prefix("  Starting items: ")
-        .unwrap()
-        .replace(' ', "");
-    let items: Result<Vec<i32>, _> = items.split(',').map(|x| x.parse::<i32>()).collect();
-    let items: VecDeque<i32> = VecDeque::from(items.unwrap());
+/// The notes describe impossible monkeys, or the worry levels do not fit in the chosen type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonkeyError {
+    Parse(ParseError),
+    Overflow(OverflowError),
+}
 
-    let operation_expression: &str = buffer[2].split("= ").last().unwrap();
+impl fmt::Display for MonkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MonkeyError::Parse(error) => error.fmt(f),
+            MonkeyError::Overflow(error) => error.fmt(f),
+        }
+    }
+}
 
-    let re = Regex::new(r"[*+-/]").unwrap();
-    let operation_operator = re.captures_iter(operation_expression).last().unwrap()[0]
-        .chars()
-        .last()
-        .unwrap();
+impl Error for MonkeyError {}
 
-    let operation_number_str: Vec<&str> = operation_expression.matches(char::is_numeric).collect();
-    let mut operation_number: Option<i32> = None;
+impl From<ParseError> for MonkeyError {
+    fn from(error: ParseError) -> Self {
+        MonkeyError::Parse(error)
+    }
+}
 
-    if !operation_number_str.is_empty() {
-        operation_number = Some(operation_number_str.join("").parse().unwrap());
+impl From<OverflowError> for MonkeyError {
+    fn from(error: OverflowError) -> Self {
+        MonkeyError::Overflow(error)
     }
+}
 
-    let divisible_by: Vec<&str> = buffer[3].matches(char::is_numeric).collect();
-    let divisible_by: i32 = divisible_by.join("").parse().unwrap();
+/// Parses the `k`-th line of the notes without its indentation, attaching the line to the errors.
+fn note<'a, T>(
+    block: &Block<'a>,
+    k: usize,
+    parser: impl FnOnce(&'a str) -> Result<T, ParseError>,
+) -> Result<T, ParseError> {
+    let text = block.lines[k];
+    let line = text.trim_start();
+    let indent = text.len() - line.len();
+    parser(line).map_err(|mut error| {
+        error.column += indent;
+        error.in_line(block.line + k, text)
+    })
+}
 
-    let divisible_target: Vec<&str> = buffer[4].matches(char::is_numeric).collect();
-    let divisible_target: i32 = divisible_target.join("").parse().unwrap();
+/// Parses a field and checks it with `is_valid`, reporting `expected` otherwise.
+fn checked_field<T: FromStr>(
+    field: (usize, &str),
+    is_valid: impl FnOnce(&T) -> bool,
+    expected: &str,
+) -> Result<T, ParseError> {
+    let value = parse_field(Some(field))?;
+    if !is_valid(&value) {
+        return Err(ParseError::new(field.0, expected).with_length(field.1.chars().count()));
+    }
+    Ok(value)
+}
 
-    let indivisible_target: Vec<&str> = buffer[5].matches(char::is_numeric).collect();
-    let indivisible_target: i32 = indivisible_target.join("").parse().unwrap();
+/// Returns the monkey described by the notes, without dividing the worry levels after the
+/// inspections.
+fn create_monkey(
+    block: &Block,
+    index: usize,
+    monkeys_number: usize,
+) -> Result<Monkey<u64>, ParseError> {
+    if block.lines.len() != 6 {
+        return Err(ParseError::whole_line(
+            block.line,
+            block.lines[0],
+            format!(
+                "expected 6 lines describing the monkey, found {}",
+                block.lines.len()
+            ),
+        ));
+    }
 
-    Monkey {
-        items,
+    note(block, 0, |line| {
+        let fields = scan_fields(line, "Monkey {}:")?;
+        checked_field(
+            fields[0],
+            |&number: &usize| number == index,
+            &format!("expected {index}"),
+        )
+    })?;
+
+    let items = note(block, 1, |line| {
+        let Some(items) = line.strip_prefix("Starting items:") else {
+            return Err(ParseError::new(1, "expected `Starting items: ITEMS`")
+                .with_length(line.chars().count()));
+        };
+        let offset = line.len() - items.len();
+        signed_integers::<u64>(items).map_err(|mut error| {
+            error.column += offset;
+            error
+        })
+    })?;
+
+    let (operation_operator, operation_number) = note(block, 2, |line| {
+        let fields = scan_fields(line, "Operation: new = old {} {}")?;
+        let operator = checked_field(
+            fields[0],
+            |operator| "+-*/".contains(*operator),
+            "expected `+`, `-`, `*` or `/`",
+        )?;
+        let operand = match fields[1].1 {
+            "old" => None,
+            _ => Some(checked_field(
+                fields[1],
+                |&number: &u64| operator != '/' || number != 0,
+                "expected a divisor other than 0",
+            )?),
+        };
+        Ok((operator, operand))
+    })?;
+
+    let divisible_by = note(block, 3, |line| {
+        let fields = scan_fields(line, "Test: divisible by {}")?;
+        checked_field(
+            fields[0],
+            |&divisor: &u64| divisor != 0,
+            "expected a divisor other than 0",
+        )
+    })?;
+    let target = |line: &str, pattern: &str| {
+        let fields = scan_fields(line, pattern)?;
+        checked_field(
+            fields[0],
+            |&target: &usize| target < monkeys_number,
+            &format!("expected a monkey from 0 to {}", monkeys_number - 1),
+        )
+    };
+    let divisible_target = note(block, 4, |line| target(line, "If true: throw to monkey {}"))?;
+    let indivisible_target = note(block, 5, |line| {
+        target(line, "If false: throw to monkey {}")
+    })?;
+
+    Ok(Monkey {
+        items: items.into(),
         operation_operator,
         operation_number,
-        inspection_divider,
+        inspection_divider: 1,
         divisible_by,
         divisible_target,
         indivisible_target,
-    }
+    })
 }
 
-fn import_monkeys(input: &str, inspection_divider: i32) -> Vec<Monkey> {
-    let mut monkeys: Vec<Monkey> = vec![];
-    let mut buffer: Vec<&str> = vec![];
-
-    for line in input.lines() {
-        if line.is_empty() {
-            monkeys.push(create_monkey(&buffer, inspection_divider));
-            buffer.clear();
-        } else {
-            buffer.push(line);
-        }
+impl Monkey<u64> {
+    /// Returns the monkey with `W` as the worry level type.
+    fn convert<W: TryFrom<u64>>(
+        &self,
+        inspection_divider: u64,
+    ) -> Result<Monkey<W>, OverflowError> {
+        Ok(Monkey {
+            items: self
+                .items
+                .iter()
+                .copied()
+                .map(checked::convert)
+                .collect::<Result<_, _>>()?,
+            operation_operator: self.operation_operator,
+            operation_number: self.operation_number.map(checked::convert).transpose()?,
+            inspection_divider: checked::convert(inspection_divider)?,
+            divisible_by: checked::convert(self.divisible_by)?,
+            divisible_target: self.divisible_target,
+            indivisible_target: self.indivisible_target,
+        })
     }
-    monkeys.push(create_monkey(&buffer, inspection_divider));
+}
 
-    monkeys
+/// The monkeys described by the notes and the least common multiple of their divisors.
+pub struct Notes {
+    monkeys: Vec<Monkey<u64>>,
+    modulo: u64,
 }
 
-fn solve(input: &str, rounds: i32, inspection_divider: i32) -> i64 {
-    let mut monkeys = import_monkeys(input, inspection_divider);
-    let mut pending_throws: [VecDeque<i32>; MAX_MONKEYS] = Default::default();
-    let mut throw_counter = [0; MAX_MONKEYS];
+pub fn parse(input: &str) -> Result<Notes, MonkeyError> {
+    let blocks = blocks(input);
+    if let Some(block) = blocks.get(MAX_MONKEYS) {
+        return Err(ParseError::whole_line(
+            block.line,
+            block.lines[0],
+            format!("expected at most {MAX_MONKEYS} monkeys"),
+        )
+        .into());
+    }
 
+    let mut monkeys: Vec<Monkey<u64>> = vec![];
     let mut modulo = 1;
-    for monkey in &monkeys {
-        modulo = lcm(modulo, monkey.divisible_by);
+
+    for (index, block) in blocks.iter().enumerate() {
+        let monkey = create_monkey(block, index, blocks.len())?;
+        let divisible_by = monkey.divisible_by;
+        modulo = lcm(modulo, divisible_by).ok_or_else(|| OverflowError {
+            operation: format!("lcm({modulo}, {divisible_by}) (u64)"),
+        })?;
+        monkeys.push(monkey);
     }
 
-    for _ in 0..rounds {
-        for (i, monkey) in monkeys.iter_mut().enumerate() {
-            while !pending_throws[i].is_empty() {
-                monkey.add_item(pending_throws[i].pop_front().unwrap());
+    Ok(Notes { monkeys, modulo })
+}
+
+/// The monkeys, advanced one round at a time. The simulation stops at the first overflow.
+#[derive(Clone)]
+struct Troop<W> {
+    monkeys: Vec<Monkey<W>>,
+    pending_throws: [VecDeque<W>; MAX_MONKEYS],
+    throw_counter: [u64; MAX_MONKEYS],
+    modulo: Option<W>,
+    rounds: usize,
+    error: Option<OverflowError>,
+}
+
+impl<W: Number> Troop<W> {
+    fn round(&mut self) -> Result<(), OverflowError> {
+        for (i, monkey) in self.monkeys.iter_mut().enumerate() {
+            while let Some(item) = self.pending_throws[i].pop_front() {
+                monkey.add_item(item);
             }
 
             while !monkey.items.is_empty() {
-                let (value, target) = monkey.throw(modulo);
-                throw_counter[i] += 1;
-                pending_throws[target as usize].push_back(value);
+                let (value, target) = monkey.throw(self.modulo.as_ref())?;
+                self.throw_counter[i] += 1;
+                self.pending_throws[target].push_back(value);
             }
         }
+        Ok(())
     }
+}
+
+impl<W: Number> Simulation for Troop<W> {
+    fn step(&mut self) {
+        match self.round() {
+            Ok(()) => self.rounds += 1,
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.error.is_some()
+    }
+}
 
+/// Simulates the monkeys with `W` as the worry level type. With `reduce`, the worry levels are
+/// kept modulo the least common multiple of the divisors, which does not change the tests as long
+/// as the worry levels are not divided (with an inspection divider of 1).
+fn solve<W: Number + TryFrom<u64>>(
+    notes: &Notes,
+    rounds: usize,
+    inspection_divider: u64,
+    reduce: bool,
+) -> Result<u64, OverflowError> {
+    let monkeys = notes
+        .monkeys
+        .iter()
+        .map(|monkey| monkey.convert(inspection_divider))
+        .collect::<Result<_, _>>()?;
+    let modulo: Option<W> = match reduce {
+        true => Some(checked::convert(notes.modulo)?),
+        false => None,
+    };
+
+    let mut troop = Troop {
+        monkeys,
+        pending_throws: Default::default(),
+        throw_counter: [0; MAX_MONKEYS],
+        modulo,
+        rounds: 0,
+        error: None,
+    };
+    run_steps(&mut troop, rounds);
+    if let Some(error) = troop.error {
+        return Err(error);
+    }
+
+    let mut throw_counter = troop.throw_counter;
     throw_counter.sort();
 
-    throw_counter[throw_counter.len() - 1] * throw_counter[throw_counter.len() - 2]
+    checked::mul(
+        throw_counter[throw_counter.len() - 1],
+        throw_counter[throw_counter.len() - 2],
+    )
+}
+
+pub fn solve1(notes: &Notes) -> Result<u64, OverflowError> {
+    // The worry levels are divided by 3, so they cannot be reduced.
+    if checked::big_integers_enabled() {
+        return solve::<BigUint>(notes, 20, 3, false);
+    }
+    solve::<i64>(notes, 20, 3, false)
+}
+
+pub fn solve2(notes: &Notes) -> Result<u64, OverflowError> {
+    solve::<i64>(notes, 10000, 1, true)
 }
 
-fn part1(input: &str) -> i64 {
-    solve(input, 20, 3)
+pub fn part1(input: &str) -> Result<u64, MonkeyError> {
+    Ok(solve1(&parse(input)?)?)
 }
 
-fn part2(input: &str) -> i64 {
-    solve(input, 10000, 1)
+pub fn part2(input: &str) -> Result<u64, MonkeyError> {
+    Ok(solve2(&parse(input)?)?)
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 10605);
 
-    let result = part1(&get_input(DAY, false));
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 54036);
+
+    let result = solve::<BigUint>(&parse(&get_input(DAY, false)).unwrap(), 20, 3, false);
+    assert_eq!(result, Ok(54036));
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 2713310158);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 13237873355);
+
+    // Without the reduction, the worry levels overflow instead of wrapping.
+    let result = solve::<i64>(&parse(&get_input(DAY, true)).unwrap(), 10000, 1, false);
+    assert!(matches!(result, Err(error) if error.operation.ends_with("(i64)")));
+
+    let input = get_input(DAY, true).replace("throw to monkey 3", "throw to monkey 4");
+    let Err(MonkeyError::Parse(error)) = part2(&input) else {
+        panic!("Expected a parse error.");
+    };
+    assert_eq!((error.line, error.column), (6, 31));
+    assert_eq!(error.message, "expected a monkey from 0 to 3");
+}
+
+/// Generates the notes about 2 to 8 monkeys. Only the first monkey can square the worry levels,
+/// so they stay small enough for unbounded integers.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = String> {
+    let divisor = prop::sample::select(vec![2_u64, 3, 5, 7, 11, 13, 17, 19, 23]);
+    let operation = prop_oneof![
+        (Just('+'), prop::option::of(1..20_u64)),
+        (Just('*'), (1..20_u64).prop_map(Some)),
+    ];
+    (2..=MAX_MONKEYS)
+        .prop_flat_map(move |monkeys_number| {
+            let monkey = (
+                prop::collection::vec(1..100_u64, 0..5),
+                operation.clone(),
+                divisor.clone(),
+                0..monkeys_number - 1,
+                0..monkeys_number - 1,
+            );
+            (prop::collection::vec(monkey, monkeys_number), any::<bool>())
+        })
+        .prop_map(|(monkeys, squaring)| {
+            let mut notes = vec![];
+            for (i, (items, (operator, operand), divisor, first, second)) in
+                monkeys.into_iter().enumerate()
+            {
+                let items: Vec<String> = items.iter().map(u64::to_string).collect();
+                let operation = match (i, squaring, operand) {
+                    (0, true, _) => "* old".to_string(),
+                    (_, _, Some(operand)) => format!("{operator} {operand}"),
+                    (_, _, None) => format!("{operator} old"),
+                };
+                // The targets skip the monkey itself.
+                let target = |target: usize| target + (target >= i) as usize;
+                notes.push(format!(
+                    "Monkey {i}:\n  Starting items: {}\n  Operation: new = old {operation}\n  \
+                     Test: divisible by {divisor}\n    If true: throw to monkey {}\n    \
+                     If false: throw to monkey {}",
+                    items.join(", "),
+                    target(first),
+                    target(second),
+                ));
+            }
+            notes.join("\n\n")
+        })
+}
+
+#[cfg(test)]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn property_reduction_keeps_the_tests(input in input_strategy()) {
+        // Keeping the worry levels modulo the least common multiple of the divisors does not
+        // change where the items are thrown.
+        let notes = parse(&input).unwrap();
+        let reduced = solve::<i64>(&notes, 10, 1, true);
+        let exact = solve::<BigUint>(&notes, 10, 1, false);
+        prop_assert!(reduced.is_ok());
+        prop_assert_eq!(reduced, exact);
+    }
+
+    #[test]
+    fn property_part1_is_exact(input in input_strategy()) {
+        let notes = parse(&input).unwrap();
+        if let Ok(result) = solve1(&notes) {
+            prop_assert_eq!(Ok(result), solve::<BigUint>(&notes, 20, 3, false));
+        }
+    }
+
+    #[test]
+    fn property_worry_types_agree(input in input_strategy()) {
+        let notes = parse(&input).unwrap();
+        prop_assert_eq!(solve::<u64>(&notes, 20, 1, true), solve::<i64>(&notes, 20, 1, true));
+    }
 }
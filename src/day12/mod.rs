@@ -1,16 +1,24 @@
 // https://adventofcode.com/2022/day/12
 
 use crate::get_input;
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::render::{renderer, Color};
+#[cfg(test)]
+use proptest::prelude::*;
 use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::io;
 
 const DAY: u8 = 12;
 const MAX_NODES: usize = 64;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
 fn update_neighbour_distance(
@@ -40,7 +48,27 @@ fn update_neighbour_distance(
     false
 }
 
-fn dijkstra(nodes: &Vec<Vec<char>>, start_nodes: &[(usize, usize)]) -> i32 {
+fn heightmap_color(&(node, visited): &(char, bool)) -> Color {
+    let height = match node {
+        'S' => 'a',
+        'E' => 'z',
+        _ => node,
+    };
+    let shade = (height as u32 - 'a' as u32) as u8 * 8 + 40;
+
+    if visited {
+        return [shade, shade / 2, 40];
+    }
+    [40, shade, 60]
+}
+
+fn heightmap_grid(nodes: &[Vec<char>], visited: &HashSet<(usize, usize)>) -> Grid<(char, bool)> {
+    Grid::from_fn(nodes[0].len(), nodes.len(), |x, y| {
+        (nodes[y][x], visited.contains(&(y, x)))
+    })
+}
+
+fn dijkstra(nodes: &[Vec<char>], start_nodes: &[(usize, usize)]) -> io::Result<i32> {
     let mut path = VecDeque::new();
     let mut visited = HashSet::new();
     let mut distance = vec![vec![i32::MAX; nodes[0].len()]; nodes.len()];
@@ -50,18 +78,36 @@ fn dijkstra(nodes: &Vec<Vec<char>>, start_nodes: &[(usize, usize)]) -> i32 {
         distance[node.0][node.1] = 0;
     }
 
-    let mut final_node = path[0];
+    // The heightmap was checked to have an end.
+    let final_node = nodes
+        .iter()
+        .enumerate()
+        .find_map(|(i, row)| Some((i, row.iter().position(|&node| node == 'E')?)))
+        .unwrap();
+    let name = if start_nodes.len() > 1 {
+        "day12_part2"
+    } else {
+        "day12_part1"
+    };
+    let size = (nodes[0].len(), nodes.len());
+    let mut renderer = renderer(name, size, heightmap_color, 4, 1)?;
+    let mut rendered_distance = 0;
 
     while !path.is_empty() {
         let current_node = path.pop_front().unwrap();
         if visited.contains(&current_node) {
             continue;
         }
-        if nodes[current_node.0][current_node.1] == 'E' {
-            final_node = current_node;
-        }
         visited.insert(current_node);
 
+        // Render the explored area once per distance from the start.
+        if let Some(renderer) = &mut renderer {
+            if distance[current_node.0][current_node.1] > rendered_distance {
+                rendered_distance = distance[current_node.0][current_node.1];
+                renderer.step(|| heightmap_grid(nodes, &visited));
+            }
+        }
+
         for relative_position in [(-1, 0), (0, -1), (1, 0), (0, 1)] {
             if (relative_position.0 == -1 && current_node.0 == 0)
                 || (relative_position.1 == -1 && current_node.1 == 0)
@@ -93,16 +139,74 @@ fn dijkstra(nodes: &Vec<Vec<char>>, start_nodes: &[(usize, usize)]) -> i32 {
         }
     }
 
-    distance[final_node.0][final_node.1]
+    if let Some(renderer) = renderer {
+        renderer.finish(heightmap_grid(nodes, &visited));
+    }
+
+    Ok(distance[final_node.0][final_node.1])
 }
 
-fn solve(input: &str, multiple_starting_nodes: bool) -> i32 {
-    let mut nodes: Vec<Vec<char>> = Vec::with_capacity(MAX_NODES);
-    let mut start_nodes = Vec::new();
+/// Checks that the heightmap is a rectangle of heights with one start and one end.
+fn check_heightmap(input: &str) -> Result<(), ParseError> {
+    let width = input.lines().next().map_or(0, |line| line.chars().count());
+    let mut marks = (0, 0);
+
     for (i, line) in input.lines().enumerate() {
-        let mut nodes_line = Vec::with_capacity(MAX_NODES);
         for (j, char) in line.chars().enumerate() {
-            nodes_line.push(char);
+            let count = match char {
+                'S' => &mut marks.0,
+                'E' => &mut marks.1,
+                'a'..='z' => continue,
+                _ => {
+                    return Err(ParseError::new(
+                        j + 1,
+                        format!("expected a height from `a` to `z`, `S` or `E`, found `{char}`"),
+                    )
+                    .in_line(i + 1, line))
+                }
+            };
+            *count += 1;
+            if *count > 1 {
+                return Err(
+                    ParseError::new(j + 1, format!("expected only one `{char}`"))
+                        .in_line(i + 1, line),
+                );
+            }
+        }
+        if line.chars().count() != width {
+            return Err(ParseError::whole_line(
+                i + 1,
+                line,
+                format!("expected {width} heights, as many as on the first line"),
+            ));
+        }
+    }
+
+    let line = input.lines().count() + 1;
+    match marks {
+        (0, _) => Err(ParseError::new(1, "expected a start `S` in the heightmap").on_line(line)),
+        (_, 0) => Err(ParseError::new(1, "expected an end `E` in the heightmap").on_line(line)),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the heights of the heightmap, row by row.
+pub fn parse(input: &str) -> Result<Vec<Vec<char>>, ParseError> {
+    check_heightmap(input)?;
+
+    let mut nodes: Vec<Vec<char>> = Vec::with_capacity(MAX_NODES);
+    for line in input.lines() {
+        let mut nodes_line = Vec::with_capacity(MAX_NODES);
+        nodes_line.extend(line.chars());
+        nodes.push(nodes_line);
+    }
+    Ok(nodes)
+}
+
+fn solve(nodes: &[Vec<char>], multiple_starting_nodes: bool) -> io::Result<i32> {
+    let mut start_nodes = Vec::new();
+    for (i, nodes_line) in nodes.iter().enumerate() {
+        for (j, &char) in nodes_line.iter().enumerate() {
             if char == 'S' {
                 start_nodes.push((i, j));
             }
@@ -110,34 +214,98 @@ fn solve(input: &str, multiple_starting_nodes: bool) -> i32 {
                 start_nodes.push((i, j));
             }
         }
-        nodes.push(nodes_line);
     }
 
-    dijkstra(&nodes, &start_nodes)
+    dijkstra(nodes, &start_nodes)
+}
+
+pub fn solve1(nodes: &[Vec<char>]) -> io::Result<i32> {
+    solve(nodes, false)
 }
 
-fn part1(input: &str) -> i32 {
-    solve(input, false)
+pub fn solve2(nodes: &[Vec<char>]) -> io::Result<i32> {
+    solve(nodes, true)
 }
 
-fn part2(input: &str) -> i32 {
-    solve(input, true)
+pub fn part1(input: &str) -> Result<i32, Box<dyn Error>> {
+    Ok(solve1(&parse(input)?)?)
+}
+
+pub fn part2(input: &str) -> Result<i32, Box<dyn Error>> {
+    Ok(solve2(&parse(input)?)?)
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 31);
 
-    let result = part1(&get_input(DAY, false));
+    // The end is higher than every other position.
+    let result = part1("SbE").unwrap();
+    assert_eq!(result, i32::MAX);
+
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 370);
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 29);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 363);
+
+    let error = parse("Sab\nc9E").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 2));
+    let error = parse("Sab\nbcd").unwrap_err();
+    assert_eq!(
+        (error.line, error.message.as_str()),
+        (3, "expected an end `E` in the heightmap")
+    );
+}
+
+/// Generates a heightmap with heights up to `highest`, and returns the positions of the start
+/// and the end with it.
+#[cfg(test)]
+fn input_strategy(
+    highest: char,
+) -> impl Strategy<Value = ((usize, usize), (usize, usize), String)> {
+    (1..12_usize, 2..12_usize)
+        .prop_flat_map(move |(height, width)| {
+            let row = prop::collection::vec(prop::char::range('a', highest), width);
+            (
+                prop::collection::vec(row, height),
+                (0..height, 0..width),
+                (0..height, 0..width),
+            )
+        })
+        .prop_filter("the start is the end", |(_, start, end)| start != end)
+        .prop_map(|(mut nodes, start, end)| {
+            nodes[start.0][start.1] = 'S';
+            nodes[end.0][end.1] = 'E';
+            let lines: Vec<String> = nodes.iter().map(|row| row.iter().collect()).collect();
+            (start, end, lines.join("\n"))
+        })
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_shortest_paths((start, end, input) in input_strategy('e')) {
+        let distance = start.0.abs_diff(end.0) + start.1.abs_diff(end.1);
+        let from_start = part1(&input).unwrap();
+        let from_lowest = part2(&input).unwrap();
+
+        // The start is one of the lowest positions, and no path is shorter than a straight line.
+        prop_assert!(from_lowest <= from_start);
+        prop_assert!(from_start == i32::MAX || from_start as usize >= distance);
+    }
+
+    #[test]
+    fn property_flat_heightmap((_, _, input) in input_strategy('a')) {
+        // The end is at the height `z`, so it cannot be reached without climbing.
+        prop_assert_eq!(part1(&input).unwrap(), i32::MAX);
+        prop_assert_eq!(part2(&input).unwrap(), i32::MAX);
+    }
 }
@@ -1,163 +1,190 @@
 // https://adventofcode.com/2022/day/13
 
 use crate::get_input;
-use regex::Regex;
-use std::cmp::{min, Ordering};
-use std::collections::HashMap;
-use std::str::FromStr;
+use crate::parse::{blocks, parse_lines_with, Block, ParseError};
+#[cfg(test)]
+use proptest::prelude::*;
+use std::cmp::Ordering;
+use std::error::Error;
 
 const DAY: u8 = 13;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
-}
-
-fn parse_packet(
-    packet: &str,
-    re1: &Regex,
-    re2: &Regex,
-    re3: &Regex,
-    re_empty_arrays: &Regex,
-    re_numbers: &Regex,
-) -> Vec<String> {
-    // Ugly hack - two-digit numbers do not work with the float conversion approach.
-    // Replaces only `10` because it is the only multi-digit number in the test input.
-    let packet = packet.replace("10", "91");
-
-    let packet = packet.replace(',', "");
-
-    let packet = re1.replace_all(&packet, "][$1][");
-    let packet = re2.replace_all(&packet, "[[$1][");
-    let packet = re3.replace_all(&packet, "][$1]]");
-    let packet = re_empty_arrays.replace_all(&packet, "[0]");
-
-    let matches: Vec<String> = re_numbers
-        .find_iter(&packet)
-        .map(|m| String::from(m.as_str()))
-        .collect();
-
-    matches
-}
-
-fn verify_packet_order(
-    first_packet: &str,
-    first_matches: &Vec<String>,
-    second_packet: &str,
-    second_matches: &Vec<String>,
-) -> bool {
-    for i in 0..min(first_matches.len(), second_matches.len()) {
-        let mut first_number = first_matches[i].to_string();
-        first_number.insert(1, '.');
-        let mut second_number = second_matches[i].to_string();
-        second_number.insert(1, '.');
-
-        let first_number = f32::from_str(&first_number).unwrap();
-        let second_number = f32::from_str(&second_number).unwrap();
-
-        if first_number > second_number {
-            return false;
-        } else if first_number < second_number {
-            return true;
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
+}
+
+/// Checks that the packet is a list of integers and lists, the order checks rely on it.
+fn check_packet(packet: &str) -> Result<(), ParseError> {
+    let mut depth = 0;
+    let mut previous = None;
+    let mut integer_start = None;
+
+    for (i, byte) in packet.bytes().enumerate() {
+        if i > 0 && depth == 0 {
+            return Err(ParseError::new(i + 1, "expected the end of the packet")
+                .with_length(packet[i..].chars().count()));
         }
 
-        match first_matches[i].len().cmp(&second_matches[i].len()) {
-            Ordering::Less => return true,
-            Ordering::Greater => return false,
-            _ => (),
+        let valid = matches!(
+            (previous, byte),
+            (None | Some(b'[' | b','), b'[')
+                | (Some(b'[' | b','), b'0'..=b'9')
+                | (Some(b'0'..=b'9'), b'0'..=b'9' | b',' | b']')
+                | (Some(b'[' | b']'), b']')
+                | (Some(b']'), b',')
+        );
+        if !valid {
+            let found = packet[i..].chars().next().unwrap();
+            return Err(ParseError::new(
+                i + 1,
+                format!("{}, found `{found}`", expected_after(previous)),
+            ));
         }
-    }
 
-    if first_packet.len() > second_packet.len() {
-        return false;
+        match byte {
+            b'[' => depth += 1,
+            b']' => depth -= 1,
+            _ => {}
+        }
+        previous = Some(byte);
+
+        // The integers are compared as `u32`.
+        if !byte.is_ascii_digit() {
+            integer_start = None;
+            continue;
+        }
+        let start = *integer_start.get_or_insert(i);
+        if packet[start..=i].parse::<u32>().is_err() {
+            return Err(ParseError::new(
+                start + 1,
+                format!("expected an integer up to {}", u32::MAX),
+            )
+            .with_length(i - start + 1));
+        }
     }
 
-    true
+    if depth > 0 || previous.is_none() {
+        return Err(ParseError::new(packet.len() + 1, expected_after(previous)));
+    }
+    Ok(())
 }
 
-fn packet_order(
-    first_packet: &str,
-    first_matches: &Vec<String>,
-    second_packet: &str,
-    second_matches: &Vec<String>,
-) -> Ordering {
-    if verify_packet_order(first_packet, first_matches, second_packet, second_matches) {
-        return Ordering::Less;
+fn expected_after(previous: Option<u8>) -> &'static str {
+    match previous {
+        None => "expected `[`",
+        Some(b'[') => "expected an integer, `[` or `]`",
+        Some(b',') => "expected an integer or `[`",
+        _ => "expected `,` or `]`",
     }
+}
 
-    Ordering::Greater
+fn check_packets(input: &str) -> Result<(), ParseError> {
+    parse_lines_with(input, |line| match line {
+        "" => Ok(()),
+        _ => check_packet(line),
+    })?;
+    Ok(())
 }
 
-fn part1(input: &str) -> u32 {
-    // Building these regexes in the loop changes the execution time from 16ms to 246ms.
-    let re1 = Regex::new(r"](-?\d+)\[").unwrap();
-    let re2 = Regex::new(r"\[(-?\d+)\[").unwrap();
-    let re3 = Regex::new(r"](-?\d+)]").unwrap();
-    let re_empty_arrays = Regex::new(r"\[]").unwrap();
-    let re_numbers = Regex::new(r"-?\d+").unwrap();
+/// An element of a packet: an integer, or the start or the end of a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Integer(u32),
+}
 
-    let mut result = 0;
-    let mut buffer: Vec<&str> = vec![];
-    for (i, line) in input.lines().enumerate() {
-        if line.is_empty() {
-            let first_matches =
-                parse_packet(buffer[0], &re1, &re2, &re3, &re_empty_arrays, &re_numbers);
-            let second_matches =
-                parse_packet(buffer[1], &re1, &re2, &re3, &re_empty_arrays, &re_numbers);
-            if verify_packet_order(buffer[0], &first_matches, buffer[1], &second_matches) {
-                result += (i as u32 + 1) / 3;
-            }
-            buffer.clear();
-        } else {
-            buffer.push(line);
+/// Returns the elements of a checked packet, in reverse order so they can be popped.
+fn tokens(packet: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut number: Option<u32> = None;
+    for byte in packet.bytes() {
+        if byte.is_ascii_digit() {
+            number = Some(number.unwrap_or(0) * 10 + (byte - b'0') as u32);
+            continue;
+        }
+        tokens.extend(number.take().map(Token::Integer));
+        match byte {
+            b'[' => tokens.push(Token::Open),
+            b']' => tokens.push(Token::Close),
+            _ => {}
         }
     }
+    tokens.reverse();
+    tokens
+}
 
-    result
+/// Compares two packets element by element. An integer compared with a list is wrapped in a list
+/// of its own by pushing back the integer and the end of that list.
+fn packet_order(first: &[Token], second: &[Token]) -> Ordering {
+    let mut first = first.to_vec();
+    let mut second = second.to_vec();
+
+    loop {
+        match (first.pop(), second.pop()) {
+            (Some(a), Some(b)) if a == b => {}
+            (Some(Token::Integer(a)), Some(Token::Integer(b))) => return a.cmp(&b),
+            (Some(Token::Close), Some(_)) | (None, Some(_)) => return Ordering::Less,
+            (Some(_), Some(Token::Close)) | (Some(_), None) => return Ordering::Greater,
+            (Some(integer), Some(Token::Open)) => first.extend([Token::Close, integer]),
+            (Some(Token::Open), Some(integer)) => second.extend([Token::Close, integer]),
+            (None, None) => return Ordering::Equal,
+        }
+    }
 }
 
-fn part2(input: &str) -> u32 {
-    let re1 = Regex::new(r"](-?\d+)\[").unwrap();
-    let re2 = Regex::new(r"\[(-?\d+)\[").unwrap();
-    let re3 = Regex::new(r"](-?\d+)]").unwrap();
-    let re_empty_arrays = Regex::new(r"\[]").unwrap();
-    let re_numbers = Regex::new(r"-?\d+").unwrap();
+/// The packets of a block of the list, with the block for the errors.
+pub struct Packets<'a> {
+    block: Block<'a>,
+    packets: Vec<Vec<Token>>,
+}
 
-    let first_divider_packet = "[[2]]";
-    let second_divider_packet = "[[6]]";
-    let mut packets: Vec<&str> = vec![first_divider_packet, second_divider_packet];
-    let mut packet_matches: HashMap<&str, Vec<String>> = HashMap::new();
+pub fn parse(input: &str) -> Result<Vec<Packets<'_>>, ParseError> {
+    check_packets(input)?;
 
-    let matches = parse_packet(
-        first_divider_packet,
-        &re1,
-        &re2,
-        &re3,
-        &re_empty_arrays,
-        &re_numbers,
-    );
-    packet_matches.insert(first_divider_packet, matches);
-    let matches = parse_packet(
-        second_divider_packet,
-        &re1,
-        &re2,
-        &re3,
-        &re_empty_arrays,
-        &re_numbers,
-    );
-    packet_matches.insert(second_divider_packet, matches);
+    Ok(blocks(input)
+        .into_iter()
+        .map(|block| Packets {
+            packets: block.lines.iter().map(|packet| tokens(packet)).collect(),
+            block,
+        })
+        .collect())
+}
 
-    for line in input.lines() {
-        if !line.is_empty() {
-            packets.push(line);
-            let matches = parse_packet(line, &re1, &re2, &re3, &re_empty_arrays, &re_numbers);
-            packet_matches.insert(line, matches);
+pub fn solve1(blocks: &[Packets]) -> Result<u32, ParseError> {
+    let mut result = 0;
+    for (i, Packets { block, packets }) in blocks.iter().enumerate() {
+        let [first_packet, second_packet] = &packets[..] else {
+            return Err(ParseError::whole_line(
+                block.line,
+                block.lines[0],
+                format!("expected a pair of packets, found {}", block.lines.len()),
+            ));
+        };
+        // Equal packets are considered to be in the right order.
+        if packet_order(first_packet, second_packet) != Ordering::Greater {
+            result += i as u32 + 1;
         }
     }
 
-    packets.sort_by(|a, b| packet_order(a, &packet_matches[a], b, &packet_matches[b]));
+    Ok(result)
+}
+
+pub fn solve2(blocks: &[Packets]) -> u32 {
+    let first_divider_packet = tokens("[[2]]");
+    let second_divider_packet = tokens("[[6]]");
+    let mut packets: Vec<&[Token]> = vec![&first_divider_packet, &second_divider_packet];
+    packets.extend(
+        blocks
+            .iter()
+            .flat_map(|block| block.packets.iter().map(Vec::as_slice)),
+    );
+
+    packets.sort_by(|a, b| packet_order(a, b));
 
     let first_divider_packet = packets
         .iter()
@@ -173,41 +200,157 @@ fn part2(input: &str) -> u32 {
     first_divider_packet * second_divider_packet
 }
 
+pub fn part1(input: &str) -> Result<u32, ParseError> {
+    solve1(&parse(input)?)
+}
+
+pub fn part2(input: &str) -> Result<u32, ParseError> {
+    Ok(solve2(&parse(input)?))
+}
+
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 13);
 
     let test_case = "[[[]]]\n[[]]\n\n";
-    assert_eq!(part1(test_case), 0);
+    assert_eq!(part1(test_case).unwrap(), 0);
 
     let test_case = "[0,0]\n[0,0,0]\n\n";
-    assert_eq!(part1(test_case), 1);
+    assert_eq!(part1(test_case).unwrap(), 1);
 
     let test_case = "[0,0,0]\n[0,0,0]\n\n";
-    assert_eq!(part1(test_case), 1);
+    assert_eq!(part1(test_case).unwrap(), 1);
 
     let test_case = "[0,0,0]\n[0,0]\n\n";
-    assert_eq!(part1(test_case), 0);
+    assert_eq!(part1(test_case).unwrap(), 0);
 
     let test_case = "[[]]\n[[[]]]\n\n";
-    assert_eq!(part1(test_case), 1);
+    assert_eq!(part1(test_case).unwrap(), 1);
 
     let test_case = "[10,3]\n[4]\n\n";
-    assert_eq!(part1(test_case), 0);
+    assert_eq!(part1(test_case).unwrap(), 0);
 
     let test_case = "[4]\n[10,3]\n\n";
-    assert_eq!(part1(test_case), 1);
+    assert_eq!(part1(test_case).unwrap(), 1);
+
+    let test_case = "[[],[8]]\n[[0]]\n\n";
+    assert_eq!(part1(test_case).unwrap(), 1);
 
-    let result = part1(&get_input(DAY, false));
+    let test_case = "[9]\n[10]\n\n";
+    assert_eq!(part1(test_case).unwrap(), 1);
+
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 5252);
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 140);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 20592);
+
+    let error = part2("[1,2]\n[1,,2]").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 4));
+    assert_eq!(error.message, "expected an integer or `[`, found `,`");
+    let error = part2("[[1]\n[2]").unwrap_err();
+    assert_eq!((error.line, error.column), (1, 5));
+    let error = part2("[1]]").unwrap_err();
+    assert_eq!((error.line, error.column), (1, 4));
+    let error = part2("[1,99999999999]").unwrap_err();
+    assert_eq!((error.column, error.length), (4, 10));
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+enum Packet {
+    Integer(u32),
+    List(Vec<Packet>),
+}
+
+#[cfg(test)]
+impl Packet {
+    fn text(&self) -> String {
+        match self {
+            Packet::Integer(value) => value.to_string(),
+            Packet::List(packets) => {
+                let packets: Vec<String> = packets.iter().map(Packet::text).collect();
+                format!("[{}]", packets.join(","))
+            }
+        }
+    }
+
+    /// The order of the puzzle, by following its rules directly.
+    fn compare(&self, other: &Packet) -> Ordering {
+        match (self, other) {
+            (Packet::Integer(a), Packet::Integer(b)) => a.cmp(b),
+            (Packet::List(a), Packet::List(b)) => {
+                for (a, b) in a.iter().zip(b) {
+                    let order = a.compare(b);
+                    if order != Ordering::Equal {
+                        return order;
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            (Packet::Integer(_), Packet::List(_)) => {
+                Packet::List(vec![self.clone()]).compare(other)
+            }
+            (Packet::List(_), Packet::Integer(_)) => {
+                self.compare(&Packet::List(vec![other.clone()]))
+            }
+        }
+    }
+}
+
+/// Generates a packet, which is always a list.
+#[cfg(test)]
+fn packet_strategy() -> impl Strategy<Value = Packet> {
+    let integer = (0..=10_u32).prop_map(Packet::Integer);
+    let value = integer.prop_recursive(3, 20, 4, |inner| {
+        prop::collection::vec(inner, 0..4).prop_map(Packet::List)
+    });
+    prop::collection::vec(value, 0..5).prop_map(Packet::List)
+}
+
+/// Returns whether a pair of packets is in the right order according to the first part.
+#[cfg(test)]
+fn in_right_order(first: &Packet, second: &Packet) -> bool {
+    part1(&format!("{}\n{}", first.text(), second.text())).unwrap() == 1
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_pairwise_order(first in packet_strategy(), second in packet_strategy()) {
+        let order = first.compare(&second);
+        prop_assume!(order != Ordering::Equal);
+        prop_assert_eq!(in_right_order(&first, &second), order == Ordering::Less);
+        prop_assert_eq!(in_right_order(&second, &first), order == Ordering::Greater);
+    }
+
+    #[test]
+    fn property_sorting_is_consistent(packets in prop::collection::vec(packet_strategy(), 1..10)) {
+        // Every pair is compared the same way by the sorting of the second part, so the dividers
+        // end up where the reference order puts them.
+        let dividers = [2, 6].map(|value| {
+            Packet::List(vec![Packet::List(vec![Packet::Integer(value)])])
+        });
+        let mut key = 1;
+        for (i, divider) in dividers.iter().enumerate() {
+            let before = packets
+                .iter()
+                .filter(|packet| packet.compare(divider) == Ordering::Less)
+                .count();
+            key *= before + i + 1;
+        }
+        prop_assume!(packets.iter().all(|packet| {
+            dividers.iter().all(|divider| packet.compare(divider) != Ordering::Equal)
+        }));
+
+        let texts: Vec<String> = packets.iter().map(Packet::text).collect();
+        prop_assert_eq!(part2(&texts.join("\n")).unwrap(), key as u32);
+    }
 }
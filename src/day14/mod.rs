@@ -1,53 +1,109 @@
 // https://adventofcode.com/2022/day/14
 
+use crate::checked;
 use crate::get_input;
+use crate::grid::Grid;
+use crate::parse::{parse_field, parse_lines_with, scan_fields, ParseError};
+use crate::render::{renderer, Color};
+use crate::simulation::{run_rendered, Frames, Render, Simulation};
+use crate::terminal;
+use crate::trace;
+#[cfg(test)]
+use proptest::prelude::*;
 use std::cmp::{max, min};
-use std::str::Split;
+use std::error::Error;
+use std::io;
 
 const DAY: u8 = 14;
 const X_MAX: usize = 1000;
 const X_SHIFT: usize = 100;
 const Y_MAX: usize = 168;
 const SAND_SOURCE: usize = 500 + X_SHIFT;
+/// How far the floor of the second part extends on both sides of the rocks.
+const FLOOR_SPREAD: usize = 200;
 
-#[derive(Debug)]
-struct Point {
+#[derive(Debug, Clone)]
+pub struct Point {
     x: usize,
     y: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Boundaries {
     lower: Point,
     upper: Point,
 }
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true)));
-    println!("{}", part1(&get_input(DAY, false)));
-    println!("{}", part2(&get_input(DAY, true)));
-    println!("{}", part2(&get_input(DAY, false)));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true))?);
+    println!("{}", part1(&get_input(DAY, false))?);
+    println!("{}", part2(&get_input(DAY, true))?);
+    println!("{}", part2(&get_input(DAY, false))?);
+    Ok(())
 }
 
-#[allow(dead_code)]
-fn show_cave(cave: &[Vec<char>], boundaries: &Boundaries) {
-    for y in 0..=boundaries.upper.y {
-        for x in boundaries.lower.x..=boundaries.upper.x {
-            print!("{}", cave[x][y]);
-        }
-        println!();
+fn cave_grid(cave: &[Vec<char>], boundaries: &Boundaries) -> Grid<char> {
+    Grid::from_fn(
+        boundaries.upper.x - boundaries.lower.x + 1,
+        boundaries.upper.y + 1,
+        |x, y| cave[boundaries.lower.x + x][y],
+    )
+}
+
+fn cave_color(cell: &char) -> Color {
+    match cell {
+        '#' => [120, 120, 120],
+        'o' => [230, 190, 90],
+        _ => [20, 20, 30],
     }
-    println!();
 }
 
-fn place_sand(cave: &mut [Vec<char>], boundaries: &Boundaries, stop_at_the_top: bool) -> u32 {
-    let mut result = 0;
+fn cave_frames(
+    name: &str,
+    title: &str,
+    boundaries: &Boundaries,
+    every: usize,
+) -> io::Result<Frames<char>> {
+    let size = (
+        boundaries.upper.x - boundaries.lower.x + 1,
+        boundaries.upper.y + 1,
+    );
+    Ok(Frames::new(
+        renderer(name, size, cave_color, 2, every)?,
+        terminal::animation(title, |&cell| cell, cave_color),
+        trace::recorder(name, |&cell| cell, cave_color),
+    ))
+}
+
+/// The cave, with one unit of sand dropped at every step.
+#[derive(Clone)]
+struct Sand {
+    cave: Vec<Vec<char>>,
+    boundaries: Boundaries,
+    units: usize,
+    last: (usize, usize),
+    done: bool,
+}
+
+impl Sand {
+    fn new(cave: Vec<Vec<char>>, boundaries: Boundaries) -> Self {
+        Sand {
+            cave,
+            boundaries,
+            units: 0,
+            last: (SAND_SOURCE, 0),
+            done: false,
+        }
+    }
 
-    'main: loop {
+    /// Returns where the unit of sand comes to rest, or `None` if it flows out of the cave or
+    /// the source is blocked.
+    fn drop_unit(&self) -> Option<(usize, usize)> {
+        let cave = &self.cave;
+        let boundaries = &self.boundaries;
         let mut x = SAND_SOURCE;
-        if stop_at_the_top && cave[x][0] == 'o' {
-            // show_cave(cave, boundaries);
-            return result;
+        if cave[x][0] == 'o' {
+            return None;
         }
 
         for y in 0..=boundaries.upper.y {
@@ -56,8 +112,7 @@ fn place_sand(cave: &mut [Vec<char>], boundaries: &Boundaries, stop_at_the_top:
             } else {
                 if cave[x - 1][y + 1] == '.' {
                     if x == boundaries.lower.x {
-                        // show_cave(cave, boundaries);
-                        return result;
+                        return None;
                     }
 
                     x -= 1;
@@ -66,8 +121,7 @@ fn place_sand(cave: &mut [Vec<char>], boundaries: &Boundaries, stop_at_the_top:
 
                 if cave[x + 1][y + 1] == '.' {
                     if x == boundaries.upper.x {
-                        // show_cave(cave, boundaries);
-                        return result;
+                        return None;
                     }
 
                     x += 1;
@@ -75,30 +129,91 @@ fn place_sand(cave: &mut [Vec<char>], boundaries: &Boundaries, stop_at_the_top:
                 }
             }
 
-            cave[x][y] = 'o';
-            result += 1;
-            continue 'main;
+            return Some((x, y));
         }
 
-        // show_cave(cave, boundaries);
-        return result;
+        None
     }
 }
 
-fn create_path(split: Split<&str>, cave: &mut [Vec<char>], boundaries: &mut Boundaries) {
-    let mut previous_coordinates = Point { x: X_MAX, y: X_MAX };
-    for coordinates in split {
-        let mut split_coordinates = coordinates.split(',').take(2);
-        let x: usize = split_coordinates.next().unwrap().parse().unwrap();
-        let x = x + X_SHIFT;
-        let y: usize = split_coordinates.next().unwrap().parse().unwrap();
+impl Simulation for Sand {
+    fn step(&mut self) {
+        match self.drop_unit() {
+            Some((x, y)) => {
+                self.cave[x][y] = 'o';
+                self.units += 1;
+                self.last = (x, y);
+            }
+            None => self.done = true,
+        }
+    }
 
-        // Ignore this for the first run.
-        if previous_coordinates.x == X_MAX {
-            previous_coordinates.x = x;
-            previous_coordinates.y = y;
+    fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+impl Render for Sand {
+    type Cell = char;
+
+    fn grid(&self) -> Grid<char> {
+        cave_grid(&self.cave, &self.boundaries)
+    }
+
+    fn focus(&self) -> (usize, usize) {
+        (self.last.0 - self.boundaries.lower.x, self.last.1)
+    }
+}
+
+/// Returns the points of a path in the cave, checking that it fits and only has straight lines.
+fn parse_path(line: &str) -> Result<Vec<Point>, ParseError> {
+    let mut points: Vec<Point> = vec![];
+    let mut column = 1;
+
+    for coordinates in line.split(" -> ") {
+        let at_column = |error: ParseError| ParseError {
+            column: error.column + column - 1,
+            ..error
+        };
+        let fields = scan_fields(coordinates, "{},{}").map_err(at_column)?;
+        let x: usize = parse_field(Some(fields[0])).map_err(at_column)?;
+        let y: usize = parse_field(Some(fields[1])).map_err(at_column)?;
+        // The floor of the second part has to fit in the cave on both sides.
+        let x_range = FLOOR_SPREAD - X_SHIFT..X_MAX - FLOOR_SPREAD - X_SHIFT;
+        let point = Point {
+            x: x.saturating_add(X_SHIFT),
+            y,
+        };
+
+        let expected = if !x_range.contains(&x) {
+            Some(format!(
+                "expected x from {} to {}",
+                x_range.start,
+                x_range.end - 1
+            ))
+        } else if y >= Y_MAX - 1 {
+            Some(format!("expected y below {}", Y_MAX - 1))
+        } else if points
+            .last()
+            .is_some_and(|previous| previous.x != point.x && previous.y != point.y)
+        {
+            Some("expected a horizontal or vertical line".to_string())
+        } else {
+            None
+        };
+        if let Some(expected) = expected {
+            return Err(ParseError::new(column, expected).with_length(coordinates.len()));
         }
 
+        points.push(point);
+        column += coordinates.len() + " -> ".len();
+    }
+    Ok(points)
+}
+
+fn create_path(points: &[Point], cave: &mut [Vec<char>], boundaries: &mut Boundaries) {
+    let mut previous_coordinates = points[0].clone();
+    for &Point { x, y } in points {
         let range_x = min(x, previous_coordinates.x)..=max(x, previous_coordinates.x);
         let range_y = min(y, previous_coordinates.y)..=max(y, previous_coordinates.y);
         for i in range_x {
@@ -117,21 +232,39 @@ fn create_path(split: Split<&str>, cave: &mut [Vec<char>], boundaries: &mut Boun
     }
 }
 
-fn part1(input: &str) -> u32 {
+/// Returns the points of every path of rocks.
+pub fn parse(input: &str) -> Result<Vec<Vec<Point>>, ParseError> {
+    let paths = parse_lines_with(input, parse_path)?;
+    if paths.is_empty() {
+        return Err(ParseError::new(1, "expected a path of rocks").on_line(1));
+    }
+    Ok(paths)
+}
+
+/// Draws the rocks of every path in the cave.
+fn create_paths(paths: &[Vec<Point>], cave: &mut [Vec<char>], boundaries: &mut Boundaries) {
+    for path in paths {
+        create_path(path, cave, boundaries);
+    }
+}
+
+pub fn solve1(paths: &[Vec<Point>]) -> io::Result<usize> {
     let mut cave = vec![vec!['.'; Y_MAX]; X_MAX];
     let mut boundaries = Boundaries {
         lower: Point { x: X_MAX, y: X_MAX },
         upper: Point { x: 0, y: 0 },
     };
 
-    for line in input.lines() {
-        create_path(line.split(" -> "), &mut cave, &mut boundaries);
-    }
+    create_paths(paths, &mut cave, &mut boundaries);
+
+    let frames = cave_frames("day14_part1", "Day 14, part 1", &boundaries, 5)?;
+    let mut sand = Sand::new(cave, boundaries);
+    run_rendered(&mut sand, frames, Sand::grid);
 
-    place_sand(&mut cave, &boundaries, false)
+    Ok(sand.units)
 }
 
-fn part2(input: &str) -> u32 {
+pub fn solve2(paths: &[Vec<Point>]) -> Result<usize, Box<dyn Error>> {
     let mut cave = vec![vec!['.'; Y_MAX + 2]; X_MAX];
     let mut boundaries = Boundaries {
         lower: Point {
@@ -140,35 +273,115 @@ fn part2(input: &str) -> u32 {
         },
         upper: Point { x: 0, y: 0 },
     };
-    for line in input.lines() {
-        create_path(line.split(" -> "), &mut cave, &mut boundaries);
-    }
+    create_paths(paths, &mut cave, &mut boundaries);
 
-    boundaries.lower.x -= 200;
-    boundaries.upper.x += 200;
+    boundaries.lower.x = checked::sub(boundaries.lower.x, FLOOR_SPREAD)?;
+    boundaries.upper.x = checked::add(boundaries.upper.x, FLOOR_SPREAD)?;
     boundaries.upper.y += 2;
 
-    for x in 0..X_MAX - 1 {
-        cave[x][boundaries.upper.y] = '#'
+    for column in cave.iter_mut().take(X_MAX - 1) {
+        column[boundaries.upper.y] = '#'
     }
 
-    place_sand(&mut cave, &boundaries, true)
+    let frames = cave_frames("day14_part2", "Day 14, part 2", &boundaries, 50)?;
+    let mut sand = Sand::new(cave, boundaries);
+    run_rendered(&mut sand, frames, Sand::grid);
+
+    Ok(sand.units)
+}
+
+pub fn part1(input: &str) -> Result<usize, Box<dyn Error>> {
+    Ok(solve1(&parse(input)?)?)
+}
+
+pub fn part2(input: &str) -> Result<usize, Box<dyn Error>> {
+    solve2(&parse(input)?)
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true));
+    let result = part1(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 24);
 
-    let result = part1(&get_input(DAY, false));
+    // The sand fills a bowl around the source until it blocks the source.
+    let result = part1("497,1 -> 497,3 -> 503,3 -> 503,1").unwrap();
+    assert_eq!(result, 9);
+
+    let result = part1(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 768);
+
+    let error = parse("498,4 -> 498,6\n503,4 -> 502,x").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 14));
+    let error = parse("18446744073709551615,4 -> 498,4").unwrap_err();
+    assert_eq!(error.message, "expected x from 100 to 699");
+    let error = parse("498,4 -> 496,6").unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (1, 10, 5));
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true));
+    let result = part2(&get_input(DAY, true)).unwrap();
     assert_eq!(result, 93);
 
-    let result = part2(&get_input(DAY, false));
+    let result = part2(&get_input(DAY, false)).unwrap();
     assert_eq!(result, 26686);
 }
+
+/// Generates paths of rocks below the source of the sand, and the scan listing them.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<Vec<(usize, usize)>>, String)> {
+    let segment = (any::<bool>(), -8..=8_i32);
+    let path = (
+        (480..520_usize, 1..30_usize),
+        prop::collection::vec(segment, 1..5),
+    )
+        .prop_map(|(start, segments)| {
+            let mut points = vec![start];
+            for (horizontal, length) in segments {
+                let (x, y) = *points.last().unwrap();
+                let point = match horizontal {
+                    true => (x.saturating_add_signed(length as isize), y),
+                    false => (x, y.saturating_add_signed(length as isize).max(1)),
+                };
+                points.push(point);
+            }
+            points
+        });
+    prop::collection::vec(path, 1..6).prop_map(|paths| {
+        let lines: Vec<String> = paths
+            .iter()
+            .map(|path| {
+                let points: Vec<String> = path.iter().map(|(x, y)| format!("{x},{y}")).collect();
+                points.join(" -> ")
+            })
+            .collect();
+        (paths, lines.join("\n"))
+    })
+}
+
+#[cfg(test)]
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn property_sand_fits_under_the_source((paths, input) in input_strategy()) {
+        let mut rocks = std::collections::HashSet::new();
+        for path in &paths {
+            for points in path.windows(2) {
+                let [(x1, y1), (x2, y2)] = points else { unreachable!() };
+                for x in *x1.min(x2)..=*x1.max(x2) {
+                    for y in *y1.min(y2)..=*y1.max(y2) {
+                        rocks.insert((x, y));
+                    }
+                }
+            }
+        }
+
+        // With the floor, the sand and the rocks fit in the triangle below the source.
+        let floor = rocks.iter().map(|&(_, y)| y).max().unwrap() + 2;
+        let in_triangle = rocks.iter().filter(|&&(x, y)| x.abs_diff(500) <= y).count();
+        let with_floor = part2(&input).unwrap();
+        prop_assert!(with_floor + in_triangle <= floor * floor);
+        prop_assert!(part1(&input).unwrap() <= with_floor);
+    }
+}
@@ -1,110 +1,129 @@
 // https://adventofcode.com/2022/day/15
 
+use crate::checked::{self, OverflowError};
 use crate::get_input;
-use regex::Regex;
+use crate::parse::{parse_field, parse_lines_with, scan_fields, ParseError};
+#[cfg(test)]
+use proptest::prelude::*;
 use std::collections::HashSet;
+use std::error::Error;
 use std::ops::RangeInclusive;
-use std::str::FromStr;
 
 const DAY: u8 = 15;
 const MAX: i32 = 4_000_000;
 
-pub fn main() {
-    println!("{}", part1(&get_input(DAY, true), 10));
-    println!("{}", part1(&get_input(DAY, false), 2_000_000));
-    println!("{}", part2(&get_input(DAY, true), 29));
-    println!("{}", part2(&get_input(DAY, false), MAX));
+pub fn main() -> Result<(), Box<dyn Error>> {
+    println!("{}", part1(&get_input(DAY, true), 10)?);
+    println!("{}", part1(&get_input(DAY, false), 2_000_000)?);
+    println!("{}", part2(&get_input(DAY, true), 29)?);
+    println!("{}", part2(&get_input(DAY, false), MAX)?);
+    Ok(())
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
-struct Point {
+pub struct Point {
     x: i32,
     y: i32,
 }
 
 impl Point {
-    fn distance(&self, other: &Self) -> u32 {
-        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    /// The distances between two `i32` points take up to 33 bits.
+    fn distance(&self, other: &Self) -> u64 {
+        u64::from(self.x.abs_diff(other.x)) + u64::from(self.y.abs_diff(other.y))
     }
 }
 
-fn chord_range(radius: u32, sensor: &Point, tested_line: i32) -> Option<RangeInclusive<i32>> {
-    let diameter = 2 * radius + 1;
-    let tested_distance = sensor.y.abs_diff(tested_line);
-    let chord_length = diameter.saturating_sub(2 * tested_distance);
+/// Returns the sensor and its closest beacon.
+fn parse_report(line: &str) -> Result<(Point, Point), ParseError> {
+    let fields = scan_fields(
+        line,
+        "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+    )?;
+    let mut coordinates = [0_i32; 4];
+    for (coordinate, field) in coordinates.iter_mut().zip(fields) {
+        *coordinate = parse_field(Some(field))?;
+    }
+    let [x1, y1, x2, y2] = coordinates;
+    Ok((Point { x: x1, y: y1 }, Point { x: x2, y: y2 }))
+}
+
+fn chord_range(
+    radius: u64,
+    sensor: &Point,
+    tested_line: i32,
+) -> Result<Option<RangeInclusive<i64>>, OverflowError> {
+    let diameter = checked::add(checked::mul(radius, 2)?, 1)?;
+    let tested_distance = u64::from(sensor.y.abs_diff(tested_line));
+    let chord_length = diameter.saturating_sub(checked::mul(tested_distance, 2)?);
 
     if chord_length == 0 {
-        return None;
+        return Ok(None);
     }
 
-    let half_chord = (chord_length as i32 - 1) / 2;
-    let start = sensor.x - half_chord;
-    let end = sensor.x + half_chord;
-    Some(start..=end)
+    let half_chord: i64 = checked::convert((chord_length - 1) / 2)?;
+    let start = checked::sub(i64::from(sensor.x), half_chord)?;
+    let end = checked::add(i64::from(sensor.x), half_chord)?;
+    Ok(Some(start..=end))
 }
 
-fn part1(input: &str, tested_line: i32) -> u32 {
-    let re = Regex::new(r"(-?\d+).*?(-?\d+).*?(-?\d+).*?(-?\d+)").unwrap();
-    let mut result = 0;
-    let mut found_beacons: HashSet<Point> = HashSet::new();
-    let mut ranges: Vec<RangeInclusive<i32>> = vec![];
-    let mut merged_ranges: Vec<RangeInclusive<i32>> = vec![];
-
-    for line in input.lines() {
-        let captures = re.captures_iter(line).last().unwrap();
-        let x1 = i32::from_str(&captures[1]).unwrap();
-        let y1 = i32::from_str(&captures[2]).unwrap();
-        let x2 = i32::from_str(&captures[3]).unwrap();
-        let y2 = i32::from_str(&captures[4]).unwrap();
+/// Returns every sensor and its closest beacon.
+pub fn parse(input: &str) -> Result<Vec<(Point, Point)>, ParseError> {
+    parse_lines_with(input, parse_report)
+}
 
-        let sensor = Point { x: x1, y: y1 };
-        let beacon = Point { x: x2, y: y2 };
+pub fn solve1(reports: &[(Point, Point)], tested_line: i32) -> Result<u64, OverflowError> {
+    let mut result = 0_u64;
+    let mut occupied = 0_u64;
+    let mut found_beacons: HashSet<Point> = HashSet::new();
+    let mut ranges: Vec<RangeInclusive<i64>> = vec![];
+    let mut merged_ranges: Vec<RangeInclusive<i64>> = vec![];
 
-        if sensor.y == tested_line && !found_beacons.contains(&sensor) {
-            result -= 1;
+    for (sensor, beacon) in reports {
+        if sensor.y == tested_line && !found_beacons.contains(sensor) {
+            occupied += 1;
             found_beacons.insert(sensor.clone());
-            ranges.push(sensor.x..=sensor.x);
+            ranges.push(sensor.x.into()..=sensor.x.into());
         }
-        if beacon.y == tested_line && sensor != beacon && !found_beacons.contains(&beacon) {
-            result -= 1;
+        if beacon.y == tested_line && sensor != beacon && !found_beacons.contains(beacon) {
+            occupied += 1;
             found_beacons.insert(beacon.clone());
-            ranges.push(beacon.x..=beacon.x);
+            ranges.push(beacon.x.into()..=beacon.x.into());
         }
 
-        let radius = sensor.distance(&beacon);
-        if sensor.y.abs_diff(tested_line) <= radius {
-            let chord_range = chord_range(radius, &sensor, tested_line);
-            ranges.push(chord_range.unwrap());
+        let radius = sensor.distance(beacon);
+        if u64::from(sensor.y.abs_diff(tested_line)) <= radius {
+            ranges.extend(chord_range(radius, sensor, tested_line)?);
         }
     }
 
     ranges.sort_by(|a, b| a.start().cmp(b.start()));
 
-    for range in &ranges {
-        if merged_ranges.is_empty() {
-            merged_ranges.push(range.clone());
-            continue;
-        }
-        for i in 0..merged_ranges.len() {
-            let merged_range = &merged_ranges[i];
-
-            if range.start() <= merged_range.end() && range.end() >= merged_range.start() {
-                let start = *range.start().min(merged_range.start());
+    // The ranges are sorted by their start, so each one either extends the last merged range or
+    // starts after it.
+    for range in ranges {
+        match merged_ranges.last_mut() {
+            Some(merged_range) if range.start() <= merged_range.end() => {
                 let end = *range.end().max(merged_range.end());
-                let new_range = start..=end;
-                merged_ranges[i] = new_range;
+                *merged_range = *merged_range.start()..=end;
             }
+            _ => merged_ranges.push(range),
         }
     }
 
     for range in merged_ranges {
-        result += range.end() - range.start() + 1;
+        let length: u64 = checked::convert(range.end().abs_diff(*range.start()))?;
+        result = checked::add(result, checked::add(length, 1)?)?;
     }
 
-    result as u32
+    // The positions with a beacon or a sensor are not counted.
+    checked::sub(result, occupied)
 }
 
-fn check_point(point: &Point, sensors_and_beacons: &Vec<(Point, u32)>) -> bool {
+pub fn part1(input: &str, tested_line: i32) -> Result<u64, Box<dyn Error>> {
+    Ok(solve1(&parse(input)?, tested_line)?)
+}
+
+fn check_point(point: &Point, sensors_and_beacons: &Vec<(Point, u64)>) -> bool {
     // Check if the point is outside of the range of all sensors.
     for (sensor, distance) in sensors_and_beacons {
         if *distance >= sensor.distance(point) {
@@ -114,55 +133,45 @@ fn check_point(point: &Point, sensors_and_beacons: &Vec<(Point, u32)>) -> bool {
     true
 }
 
-fn part2(input: &str, max: i32) -> u64 {
-    let re = Regex::new(r"(-?\d+).*?(-?\d+).*?(-?\d+).*?(-?\d+)").unwrap();
-    let mut sensors_with_distance: Vec<(Point, u32)> = vec![];
+pub fn solve2(reports: &[(Point, Point)], max: i32) -> Result<u64, Box<dyn Error>> {
+    let mut sensors_with_distance: Vec<(Point, u64)> = vec![];
     let mut empty_point = Point { x: -1, y: -1 };
 
-    for line in input.lines() {
-        let captures = re.captures_iter(line).last().unwrap();
-        let x1 = i32::from_str(&captures[1]).unwrap();
-        let y1 = i32::from_str(&captures[2]).unwrap();
-        let x2 = i32::from_str(&captures[3]).unwrap();
-        let y2 = i32::from_str(&captures[4]).unwrap();
-
-        let sensor = Point { x: x1, y: y1 };
-        let beacon = Point { x: x2, y: y2 };
-        let radius = sensor.distance(&beacon);
+    for (sensor, beacon) in reports {
+        let radius = sensor.distance(beacon);
 
-        sensors_with_distance.push((sensor, radius));
+        sensors_with_distance.push((sensor.clone(), radius));
     }
 
+    // The corners are computed in `i64`, which holds the sums of `i32` coordinates and 33-bit
+    // distances.
+    let area = 0..=i64::from(max);
     'main: for (sensor1, distance) in &sensors_with_distance {
+        let reach1: i64 = checked::convert(checked::add(*distance, 1)?)?;
+        let (x1, y1) = (i64::from(sensor1.x), i64::from(sensor1.y));
         for (sensor2, distance2) in &sensors_with_distance {
             if sensor1 == sensor2 {
                 continue;
             }
+            let reach2: i64 = checked::convert(checked::add(*distance2, 1)?)?;
+            let (x2, y2) = (i64::from(sensor2.x), i64::from(sensor2.y));
 
             // Generate 8 permutations of corners.
             for diff1 in [-1, 1] {
                 for diff2 in [-1, 1] {
                     for diff_common in [-1, 1] {
-                        let corner1 =
-                            sensor1.x + sensor1.y * diff_common + (*distance as i32 + 1) * diff1;
-
-                        let corner2 =
-                            sensor2.x - sensor2.y * diff_common + (*distance2 as i32 + 1) * diff2;
-
-                        let mut intersection_point = Point {
-                            x: (corner1 + corner2) / 2,
-                            y: corner1,
-                        };
-                        intersection_point.y -= intersection_point.x;
-
-                        if intersection_point.x < 0
-                            || intersection_point.y < 0
-                            || intersection_point.x > max
-                            || intersection_point.y > max
-                        {
+                        let corner1 = x1 + y1 * diff_common + reach1 * diff1;
+                        let corner2 = x2 - y2 * diff_common + reach2 * diff2;
+                        let x = (corner1 + corner2) / 2;
+                        let y = corner1 - x;
+                        if !area.contains(&x) || !area.contains(&y) {
                             continue;
                         }
 
+                        let intersection_point = Point {
+                            x: checked::convert(x)?,
+                            y: checked::convert(y)?,
+                        };
                         if check_point(&intersection_point, &sensors_with_distance) {
                             empty_point = intersection_point;
                             break 'main;
@@ -174,25 +183,145 @@ fn part2(input: &str, max: i32) -> u64 {
     }
 
     if empty_point.x < 0 {
-        panic!("You should implement _corner_ cases.")
+        return Err(
+            "no position found next to two sensor ranges, the corners are not checked".into(),
+        );
     }
-    empty_point.x as u64 * MAX as u64 + empty_point.y as u64
+    let x: u64 = checked::convert(empty_point.x)?;
+    let y: u64 = checked::convert(empty_point.y)?;
+    let tuning = checked::mul(x, checked::convert(MAX)?)?;
+    Ok(checked::add(tuning, y)?)
+}
+
+pub fn part2(input: &str, max: i32) -> Result<u64, Box<dyn Error>> {
+    solve2(&parse(input)?, max)
 }
 
 #[test]
 fn test1() {
-    let result = part1(&get_input(DAY, true), 10);
+    let result = part1(&get_input(DAY, true), 10).unwrap();
     assert_eq!(result, 26);
 
-    let result = part1(&get_input(DAY, false), 2_000_000);
+    // The ranges of the two sensors do not overlap.
+    let input = "Sensor at x=0, y=0: closest beacon is at x=1, y=0\n\
+                 Sensor at x=10, y=0: closest beacon is at x=11, y=0";
+    let result = part1(input, 1).unwrap();
+    assert_eq!(result, 2);
+
+    let result = part1(&get_input(DAY, false), 2_000_000).unwrap();
     assert_eq!(result, 4919281);
+
+    let input = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15\nSensor at x=9, y=16: beacon";
+    let error = part1(input, 10).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("line 2, column 18: expected `: closest beacon"));
+
+    // The distance between the corners of the `i32` plane does not fit in `u32`.
+    let input = "Sensor at x=2147483647, y=-2147483648: closest beacon is at x=-2147483648, \
+                 y=2147483647";
+    let result = part1(input, 0).unwrap();
+    assert_eq!(result, 2 * ((1 << 33) - 2 - (1 << 31)) + 1);
 }
 
 #[test]
 fn test2() {
-    let result = part2(&get_input(DAY, true), 20);
+    let result = part2(&get_input(DAY, true), 20).unwrap();
     assert_eq!(result, 56000011);
 
-    let result = part2(&get_input(DAY, false), MAX);
+    let result = part2(&get_input(DAY, false), MAX).unwrap();
     assert_eq!(result, 12630143363767);
+
+    // The corners of the ranges reach beyond the `i32` plane, and cover the whole area.
+    let input = "Sensor at x=2147483647, y=2147483647: closest beacon is at x=2147483647, \
+                 y=-2147483648\n\
+                 Sensor at x=-2147483648, y=-2147483648: closest beacon is at x=-2147483648, \
+                 y=2147483647";
+    let error = part2(input, 20).unwrap_err();
+    assert!(error.to_string().starts_with("no position found"));
+}
+
+/// Generates sensors with their closest beacon on a small grid, and the report listing them.
+#[cfg(test)]
+fn input_strategy() -> impl Strategy<Value = (Vec<(Point, Point)>, String)> {
+    let point = (-5..25, -5..25).prop_map(|(x, y)| Point { x, y });
+    prop::collection::vec((point.clone(), point), 1..6).prop_map(|sensors| {
+        let input = report(&sensors);
+        (sensors, input)
+    })
+}
+
+/// Generates the reports leaving exactly one position uncovered from 0 to 20, returned with them.
+///
+/// Four sensors, one in each diagonal direction from the position, reach every position of the
+/// area but that one. Other sensors are added when they do not reach it either.
+#[cfg(test)]
+fn distress_strategy() -> impl Strategy<Value = (Point, String)> {
+    let point = (-5..25, -5..25).prop_map(|(x, y)| Point { x, y });
+    let position = (0..=20, 0..=20).prop_map(|(x, y)| Point { x, y });
+    let others = prop::collection::vec((point.clone(), point), 0..4);
+    (position, 21..40, others).prop_map(|(position, offset, others)| {
+        let mut sensors: Vec<(Point, Point)> = [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+            .into_iter()
+            .map(|(dx, dy)| {
+                let sensor = Point {
+                    x: position.x + dx * offset,
+                    y: position.y + dy * offset,
+                };
+                let beacon = Point {
+                    x: sensor.x - dx * (2 * offset - 1),
+                    y: sensor.y,
+                };
+                (sensor, beacon)
+            })
+            .collect();
+        sensors.extend(
+            others
+                .into_iter()
+                .filter(|(sensor, beacon)| sensor.distance(&position) > sensor.distance(beacon)),
+        );
+        (position, report(&sensors))
+    })
+}
+
+#[cfg(test)]
+fn report(sensors: &[(Point, Point)]) -> String {
+    let lines: Vec<String> = sensors
+        .iter()
+        .map(|(sensor, beacon)| {
+            format!(
+                "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+                sensor.x, sensor.y, beacon.x, beacon.y
+            )
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Returns whether a position is within the range of a sensor, by brute force.
+#[cfg(test)]
+fn covered(sensors: &[(Point, Point)], point: &Point) -> bool {
+    sensors
+        .iter()
+        .any(|(sensor, beacon)| sensor.distance(point) <= sensor.distance(beacon))
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn property_coverage((sensors, input) in input_strategy(), tested_line in -5..25) {
+        // Neither the sensors nor the beacons are counted.
+        let count = (-100..130)
+            .map(|x| Point { x, y: tested_line })
+            .filter(|point| covered(&sensors, point))
+            .filter(|point| sensors.iter().all(|(sensor, beacon)| sensor != point && beacon != point))
+            .count();
+        prop_assert_eq!(part1(&input, tested_line).unwrap(), count as u64);
+    }
+
+    #[test]
+    fn property_distress_beacon((position, input) in distress_strategy()) {
+        let tuning = position.x as u64 * MAX as u64 + position.y as u64;
+        prop_assert_eq!(part2(&input, 20).unwrap(), tuning);
+    }
 }
@@ -0,0 +1,423 @@
+// Random valid inputs of any size for every day, the same for the same seed.
+//
+// Each generator takes a size, whose meaning depends on the day (the number of lines, of items,
+// or the side of a grid), and stays within the limits checked by the parsing of the day.
+
+use crate::day05;
+use crate::ocr;
+use crate::random::Random;
+use std::ops::RangeInclusive;
+
+fn lines(count: usize, mut line: impl FnMut() -> String) -> String {
+    let lines: Vec<String> = (0..count).map(|_| line()).collect();
+    lines.join("\n")
+}
+
+/// The calories of `size` elves, carrying 1 to 5 items each.
+fn calories(size: usize, random: &mut Random) -> String {
+    let blocks: Vec<String> = (0..size)
+        .map(|_| {
+            lines(random.range(1, 6) as usize, || {
+                random.range(1, 100_000).to_string()
+            })
+        })
+        .collect();
+    blocks.join("\n\n")
+}
+
+/// A strategy guide of `size` rounds.
+fn rounds(size: usize, random: &mut Random) -> String {
+    lines(size, || {
+        format!(
+            "{} {}",
+            random.pick(&['A', 'B', 'C']),
+            random.pick(&['X', 'Y', 'Z'])
+        )
+    })
+}
+
+/// `size` groups of three rucksacks, with one item in both compartments of every rucksack and a
+/// badge in every rucksack of a group.
+fn rucksacks(size: usize, random: &mut Random) -> String {
+    let mut letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+    let mut rucksacks = vec![];
+    for _ in 0..size {
+        // The badge, one common item per rucksack, then eight letters for each compartment, so
+        // that every rucksack and every group shares a single item.
+        random.shuffle(&mut letters);
+        let badge = letters[0];
+        for k in 0..3 {
+            let common = letters[1 + k];
+            let first_pool = &letters[4 + 16 * k..][..8];
+            let second_pool = &letters[12 + 16 * k..][..8];
+            let mut first = vec![common, badge];
+            let mut second = vec![common, random.pick(second_pool)];
+            for _ in 0..random.below(15) {
+                first.push(random.pick(first_pool));
+                second.push(random.pick(second_pool));
+            }
+            random.shuffle(&mut first);
+            random.shuffle(&mut second);
+            rucksacks.push(first.into_iter().chain(second).collect::<String>());
+        }
+    }
+    rucksacks.join("\n")
+}
+
+/// `size` pairs of section assignments.
+fn assignments(size: usize, random: &mut Random) -> String {
+    lines(size, || {
+        let (a, c) = (random.range(1, 100), random.range(1, 100));
+        let (b, d) = (a + random.range(0, 20), c + random.range(0, 20));
+        format!("{a}-{b},{c}-{d}")
+    })
+}
+
+/// Nine stacks with `size` crates in total, and `size` moves that can all be done.
+fn crates(size: usize, random: &mut Random) -> String {
+    let mut stacks = vec![vec![]; 9];
+    for _ in 0..size.max(1) {
+        stacks[random.below(9) as usize].push(random.letter('A'..='Z').to_string());
+    }
+
+    let mut lines = vec![day05::draw_stacks(&stacks), String::new()];
+
+    // The moves follow the heights of the stacks, and take any number of their crates.
+    let mut heights: Vec<usize> = stacks.iter().map(Vec::len).collect();
+    for _ in 0..size {
+        let non_empty: Vec<usize> = (0..heights.len()).filter(|&i| heights[i] > 0).collect();
+        let from = random.pick(&non_empty);
+        let to = random.below(heights.len() as u64) as usize;
+        let count = random.below(heights[from] as u64) as usize + 1;
+        heights[from] -= count;
+        heights[to] += count;
+        lines.push(format!("move {count} from {} to {}", from + 1, to + 1));
+    }
+    lines.join("\n")
+}
+
+/// A datastream of `size` letters from `a` to `m` (so without the marker of the second part),
+/// followed by 14 different letters.
+fn datastream(size: usize, random: &mut Random) -> String {
+    let mut marker: Vec<char> = ('a'..='z').collect();
+    random.shuffle(&mut marker);
+    (0..size)
+        .map(|_| random.letter('a'..='m'))
+        .chain(marker.into_iter().take(14))
+        .collect()
+}
+
+/// A transcript exploring `size` directories (with the root) of up to 4 files each.
+fn transcript(size: usize, random: &mut Random) -> String {
+    // Every directory is in one of the directories before it.
+    let mut children = vec![vec![]; size.max(1)];
+    for directory in 1..children.len() {
+        let parent = random.below(directory as u64) as usize;
+        children[parent].push(directory);
+    }
+
+    let mut lines = vec!["$ cd /".to_string()];
+    // The directories to explore, with the number of `cd ..` after them.
+    let mut pending = vec![(0, 0)];
+    while let Some((directory, returns)) = pending.pop() {
+        lines.push("$ ls".to_string());
+        for i in 0..random.below(5) {
+            lines.push(format!("{} file{i}.txt", random.range(1, 200_000)));
+        }
+        for child in &children[directory] {
+            lines.push(format!("dir d{child}"));
+        }
+
+        match children[directory].split_last() {
+            Some((&last, others)) => {
+                pending.push((last, returns + 1));
+                pending.extend(others.iter().rev().map(|&child| (child, 1)));
+            }
+            None => lines.extend((0..returns).map(|_| "$ cd ..".to_string())),
+        }
+        if let Some(&(next, _)) = pending.last() {
+            lines.push(format!("$ cd d{next}"));
+        }
+    }
+    lines.join("\n")
+}
+
+/// A square of `size` by `size` trees of random heights.
+fn trees(size: usize, random: &mut Random) -> String {
+    lines(size, || {
+        (0..size).map(|_| random.letter('0'..='9')).collect()
+    })
+}
+
+/// `size` motions of the head of the rope, of up to 20 steps.
+fn motions(size: usize, random: &mut Random) -> String {
+    lines(size, || {
+        format!(
+            "{} {}",
+            random.pick(&["U", "D", "L", "R"]),
+            random.range(1, 21)
+        )
+    })
+}
+
+/// A program of `size` instructions (at least 120), whose first 120 `addx` draw eight random
+/// letters on the screen.
+fn program(size: usize, random: &mut Random) -> String {
+    let glyphs: Vec<(char, [&str; 6])> = ocr::SMALL_FONT
+        .into_iter()
+        .filter(|(_, glyph)| glyph[0].len() == 4)
+        .collect();
+    // The first two pixels are always lit, as the register starts at 1.
+    let first: Vec<_> = glyphs
+        .iter()
+        .copied()
+        .filter(|(_, glyph)| glyph[0].starts_with("##"))
+        .collect();
+    let word: Vec<[&str; 6]> = (0..8)
+        .map(|i| random.pick(if i == 0 { &first } else { &glyphs }).1)
+        .collect();
+    let lit = |cycle: usize| {
+        let (row, column) = (cycle / 40, cycle % 40);
+        column % 5 < 4 && word[column / 5][row].as_bytes()[column % 5] == b'#'
+    };
+
+    // Every `addx` lasts two cycles, drawing two pixels with the register set by the one before.
+    let mut lines = vec![];
+    let mut register = 1;
+    for pair in 1..120 {
+        let column = (2 * pair % 40) as i64;
+        let next = match (lit(2 * pair), lit(2 * pair + 1)) {
+            (true, true) => column + 1,
+            (true, false) => column - 1,
+            (false, true) => column + 2,
+            (false, false) => -10,
+        };
+        lines.push(format!("addx {}", next - register));
+        register = next;
+    }
+    lines.push("noop".to_string());
+
+    lines.extend((120..size).map(|_| match random.below(2) {
+        0 => "noop".to_string(),
+        _ => format!("addx {}", random.range(-20, 21)),
+    }));
+    lines.join("\n")
+}
+
+/// The notes about 8 monkeys holding `size` items. Only the first monkey multiplies, and by at
+/// most 7, so the worry levels of the first part fit in `u64`.
+fn monkeys(size: usize, random: &mut Random) -> String {
+    let mut divisors = [2, 3, 5, 7, 11, 13, 17, 19];
+    random.shuffle(&mut divisors);
+    let mut items = vec![vec![]; 8];
+    for _ in 0..size {
+        items[random.below(8) as usize].push(random.range(1, 100).to_string());
+    }
+
+    let notes: Vec<String> = (0..8)
+        .map(|i| {
+            let operation = match i {
+                0 => format!("* {}", random.range(2, 8)),
+                _ => format!("+ {}", random.range(1, 9)),
+            };
+            // The targets are two other monkeys.
+            let mut targets: Vec<usize> = (0..8).filter(|&target| target != i).collect();
+            random.shuffle(&mut targets);
+            format!(
+                "Monkey {i}:\n  Starting items: {}\n  Operation: new = old {operation}\n  \
+                 Test: divisible by {}\n    If true: throw to monkey {}\n    \
+                 If false: throw to monkey {}",
+                items[i].join(", "),
+                divisors[i],
+                targets[0],
+                targets[1],
+            )
+        })
+        .collect();
+    notes.join("\n\n")
+}
+
+/// A square heightmap of `size` by `size`, rising from the start in a corner to the end in the
+/// opposite one, with random dips away from the top and right edges, which can always be climbed.
+fn heightmap(size: usize, random: &mut Random) -> String {
+    let size = size.max(2);
+    let rows: Vec<String> = (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| match (x, y) {
+                    (0, 0) => 'S',
+                    _ if (x, y) == (size - 1, size - 1) => 'E',
+                    _ => {
+                        let height = (x + y) * 25 / (2 * size - 2);
+                        let dip = match y == 0 || x == size - 1 {
+                            true => 0,
+                            false => random.below(2) as usize,
+                        };
+                        (b'a' + height.saturating_sub(dip) as u8) as char
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    rows.join("\n")
+}
+
+fn packet(depth: usize, random: &mut Random) -> String {
+    let values: Vec<String> = (0..random.below(5))
+        .map(|_| match depth > 0 && random.below(3) == 0 {
+            true => packet(depth - 1, random),
+            false => random.below(11).to_string(),
+        })
+        .collect();
+    format!("[{}]", values.join(","))
+}
+
+/// `size` pairs of packets, nested up to 4 lists deep.
+fn packets(size: usize, random: &mut Random) -> String {
+    let pairs: Vec<String> = (0..size)
+        .map(|_| format!("{}\n{}", packet(3, random), packet(3, random)))
+        .collect();
+    pairs.join("\n\n")
+}
+
+/// `size` paths of rocks with up to 4 straight lines each, in the area where the sand falls.
+fn rock_paths(size: usize, random: &mut Random) -> String {
+    lines(size, || {
+        let (mut x, mut y) = (random.range(420, 580), random.range(10, 150));
+        let mut points = vec![format!("{x},{y}")];
+        for i in 0..random.range(1, 5) {
+            match i % 2 {
+                0 => x = (x + random.range(-10, 11)).clamp(400, 600),
+                _ => y = (y + random.range(-10, 11)).clamp(1, 160),
+            }
+            points.push(format!("{x},{y}"));
+        }
+        points.join(" -> ")
+    })
+}
+
+/// `size` sensors in the area searched by the second part. Their beacons are as far as possible
+/// without covering a hidden position, so it is never covered.
+fn sensors(size: usize, random: &mut Random) -> String {
+    let hidden = (random.range(0, 4_000_001), random.range(0, 4_000_001));
+    lines(size, || loop {
+        let (x, y) = (random.range(0, 4_000_001), random.range(0, 4_000_001));
+        let distance = x.abs_diff(hidden.0) + y.abs_diff(hidden.1);
+        if distance < 2 {
+            continue;
+        }
+        let radius = distance - 1;
+        let beacon_x = x + random.range(-(radius as i64), radius as i64 + 1);
+        let rest = radius as i64 - beacon_x.abs_diff(x) as i64;
+        let beacon_y = y + if random.below(2) == 0 { rest } else { -rest };
+        break format!("Sensor at x={x}, y={y}: closest beacon is at x={beacon_x}, y={beacon_y}");
+    })
+}
+
+/// Generates an input of a size, with the random numbers.
+type Generate = fn(usize, &mut Random) -> String;
+
+pub struct Generator {
+    pub day: u8,
+    pub generate: Generate,
+    /// The sizes accepted by the solution. The first one is small, but not trivial.
+    pub sizes: RangeInclusive<usize>,
+}
+
+pub const GENERATORS: [Generator; 15] = [
+    Generator {
+        day: 1,
+        generate: calories,
+        sizes: 100..=usize::MAX,
+    },
+    Generator {
+        day: 2,
+        generate: rounds,
+        sizes: 100..=usize::MAX,
+    },
+    Generator {
+        day: 3,
+        generate: rucksacks,
+        sizes: 30..=usize::MAX,
+    },
+    Generator {
+        day: 4,
+        generate: assignments,
+        sizes: 100..=usize::MAX,
+    },
+    Generator {
+        day: 5,
+        generate: crates,
+        sizes: 50..=usize::MAX,
+    },
+    Generator {
+        day: 6,
+        generate: datastream,
+        sizes: 100..=usize::MAX,
+    },
+    Generator {
+        day: 7,
+        generate: transcript,
+        sizes: 20..=usize::MAX,
+    },
+    Generator {
+        day: 8,
+        generate: trees,
+        sizes: 12..=100,
+    },
+    Generator {
+        day: 9,
+        generate: motions,
+        sizes: 100..=usize::MAX,
+    },
+    Generator {
+        day: 10,
+        generate: program,
+        sizes: 120..=usize::MAX,
+    },
+    Generator {
+        day: 11,
+        generate: monkeys,
+        sizes: 10..=usize::MAX,
+    },
+    Generator {
+        day: 12,
+        generate: heightmap,
+        sizes: 16..=usize::MAX,
+    },
+    Generator {
+        day: 13,
+        generate: packets,
+        sizes: 20..=usize::MAX,
+    },
+    Generator {
+        day: 14,
+        generate: rock_paths,
+        sizes: 10..=usize::MAX,
+    },
+    Generator {
+        day: 15,
+        generate: sensors,
+        sizes: 5..=usize::MAX,
+    },
+];
+
+#[test]
+fn test_generators() {
+    use crate::registry::{Input, Once, SOLUTIONS};
+
+    for (i, generator) in GENERATORS.iter().enumerate() {
+        assert_eq!(generator.day as usize, i + 1);
+        let size = *generator.sizes.start();
+        let text = (generator.generate)(size, &mut Random::new(7));
+        assert_eq!(text, (generator.generate)(size, &mut Random::new(7)));
+
+        for size in [size, 2 * size] {
+            let text = (generator.generate)(size.min(*generator.sizes.end()), &mut Random::new(1));
+            let input = Input::new(size.to_string(), text, false);
+            if let Err(error) = (SOLUTIONS[i].run)(&input, &mut Once) {
+                panic!("day {}, size {size}: {error}", generator.day);
+            }
+        }
+    }
+}
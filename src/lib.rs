@@ -0,0 +1,45 @@
+// The solutions of every day, with the helpers they share.
+
+use std::fs;
+
+pub mod big_uint;
+pub mod bit_set;
+pub mod checked;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod generate;
+pub mod grid;
+pub mod number_theory;
+pub mod ocr;
+pub mod parse;
+pub mod random;
+pub mod registry;
+pub mod render;
+pub mod rope;
+pub mod simulation;
+pub mod stream;
+pub mod stress;
+pub mod terminal;
+pub mod trace;
+
+pub fn get_input(day: u8, test: bool) -> String {
+    let mut input_file = "input";
+    if test {
+        input_file = "test_input";
+    }
+    let path = format!("src/day{day:0>2}/{input_file}");
+    fs::read_to_string(path).expect("Should have been able to read the file.")
+}
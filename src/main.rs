@@ -1,73 +1,255 @@
-use std::fs;
-
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-
+use advent_of_code::*;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::time::Duration;
+
+/// Runs both parts of a day on the test and the real input.
+type Day = fn() -> Result<(), Box<dyn Error>>;
+
+const DAYS: [Day; 15] = [
+    day01::main,
+    day02::main,
+    day03::main,
+    day04::main,
+    day05::main,
+    day06::main,
+    day07::main,
+    day08::main,
+    day09::main,
+    day10::main,
+    day11::main,
+    day12::main,
+    day13::main,
+    day14::main,
+    day15::main,
+];
+
+/// Runs the selected days (all of them by default).
+///
+/// Usage: `advent_of_code [--render] [--animate] [--fps FPS] [--trace] [--big-int] [DAY...]`,
+/// where `--render` writes the states of the simulations to the `render` directory, `--animate`
+/// shows them in the terminal, `--trace` records them to the `trace` directory, and `--big-int`
+/// uses arbitrary-precision integers where supported.
+///
+/// `advent_of_code replay TRACE` steps through a recorded trace.
+///
+/// `advent_of_code stress [--seed SEED] [--limit SECONDS] [DAY...]` runs the selected days (all of
+/// them by default) on generated inputs of growing sizes, and reports their running times.
+///
+/// `advent_of_code crates PATH [--crane 9000|9001] [MOVE...]` prints the stacks of day 5 after
+/// the selected moves, and checks that undoing the moves gives back the initial stacks.
+///
+/// `advent_of_code stream DAY [PATH]` solves a day from a file, or from the standard input, without
+/// keeping the input in memory.
 fn main() {
-    println!("Day 1");
-    day01::main();
-
-    println!("\nDay 2");
-    day02::main();
-
-    println!("\nDay 3");
-    day03::main();
-
-    println!("\nDay 4");
-    day04::main();
-
-    println!("\nDay 5");
-    day05::main();
-
-    println!("\nDay 6");
-    day06::main();
-
-    println!("\nDay 7");
-    day07::main();
-
-    println!("\nDay 8");
-    day08::main();
-
-    println!("\nDay 9");
-    day09::main();
-
-    println!("\nDay 10");
-    day10::main();
+    let mut selected_days: Vec<usize> = vec![];
+    let mut animate = false;
+    let mut fps = 30;
+
+    let mut arguments = env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--render" => render::enable(),
+            "--trace" => trace::enable(),
+            "replay" => {
+                let Some(path) = arguments.next() else {
+                    invalid_arguments("missing the path of the trace");
+                };
+                if let Err(error) = trace::replay(&path) {
+                    eprintln!("Could not replay {path}: {error}");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "crates" => {
+                if animate {
+                    terminal::enable(fps);
+                }
+                let Some(path) = arguments.next() else {
+                    invalid_arguments("missing the path of the input");
+                };
+                if let Err(error) = crates(&path, arguments) {
+                    eprintln!("error: {error}");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "stream" => {
+                let Some(day) = arguments.next() else {
+                    invalid_arguments("missing the day");
+                };
+                if let Err(error) = stream(&day, arguments.next()) {
+                    eprintln!("error: {error}");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "stress" => {
+                if let Err(error) = stress(arguments) {
+                    eprintln!("error: {error}");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "--animate" => animate = true,
+            "--big-int" => checked::enable_big_integers(),
+            "--fps" => {
+                let Some(value) = arguments.next() else {
+                    invalid_arguments("missing the number of frames per second");
+                };
+                fps = value.parse().unwrap_or_else(|_| {
+                    invalid_arguments(&format!("invalid number of frames per second {value}"))
+                });
+            }
+            day => match day.parse() {
+                Ok(day) => selected_days.push(day),
+                Err(_) => invalid_arguments(&format!("unknown argument {day}")),
+            },
+        }
+    }
+    if animate {
+        terminal::enable(fps);
+    }
 
-    println!("\nDay 11");
-    day11::main();
+    let mut failed = false;
+    let mut first = true;
+    for (i, day_main) in DAYS.iter().enumerate() {
+        if !selected_days.is_empty() && !selected_days.contains(&(i + 1)) {
+            continue;
+        }
+
+        if !first {
+            println!();
+        }
+        first = false;
+
+        println!("Day {}", i + 1);
+        if let Err(error) = day_main() {
+            eprintln!("error: {error}");
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
 
-    println!("\nDay 12");
-    day12::main();
+/// Reports an invalid command line like the other errors, and exits.
+fn invalid_arguments(message: &str) -> ! {
+    eprintln!("error: {message}");
+    std::process::exit(1);
+}
 
-    println!("\nDay 13");
-    day13::main();
+/// Prints the stacks of day 5 after the selected moves (0 for the initial stacks, all of them by
+/// default) of the crane chosen with `--crane` (9000 by default), then undoes the moves from the
+/// final stacks and checks that they give back the initial ones.
+fn crates(path: &str, mut arguments: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let mut model = "9000".to_string();
+    let mut shown: Vec<usize> = vec![];
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--crane" => model = arguments.next().ok_or("missing the value of --crane")?,
+            number => shown.push(number.parse()?),
+        }
+    }
+    let crane = || -> Result<Box<dyn day05::Crane>, String> {
+        match model.as_str() {
+            "9000" => Ok(Box::new(day05::CrateMover9000)),
+            "9001" => Ok(Box::new(day05::CrateMover9001)),
+            _ => Err(format!("unknown crane {model}, expected 9000 or 9001")),
+        }
+    };
+
+    let input = fs::read_to_string(path)?;
+    let procedure = day05::parse(&input)?;
+    let moves: Vec<(usize, &str)> = procedure.moves().collect();
+    if let Some(number) = shown.iter().find(|&&number| number > moves.len()) {
+        return Err(format!("expected a move from 0 to {}, found {number}", moves.len()).into());
+    }
 
-    println!("\nDay 14");
-    day14::main();
+    let stacks = day05::replay(
+        &procedure,
+        crane()?.as_mut(),
+        |number| shown.is_empty() || shown.contains(&number),
+        |number, stacks| {
+            match number {
+                0 => println!("Initial stacks:"),
+                _ => {
+                    let (line, text) = moves[number - 1];
+                    println!("After move {number} (line {line}: {text}):");
+                }
+            }
+            println!("{}\n", day05::draw_stacks(&stacks));
+        },
+    )?;
+
+    let initial = day05::undo(&procedure, &stacks, crane()?.as_mut())?;
+    if initial != procedure.stacks() {
+        return Err("undoing the moves does not give back the initial stacks".into());
+    }
+    println!(
+        "Undoing the {} moves gives back the initial stacks.",
+        moves.len()
+    );
+    Ok(())
+}
 
-    println!("\nDay 15");
-    day15::main();
+/// Solves the day from the file, or from the standard input, with its streaming solution.
+fn stream(day: &str, path: Option<String>) -> Result<(), Box<dyn Error>> {
+    let day: u8 = day.parse()?;
+    let streaming = registry::STREAMING
+        .iter()
+        .find(|streaming| streaming.day == day)
+        .ok_or_else(|| format!("day {day} cannot read its input as a stream"))?;
+
+    let answers = match path {
+        Some(path) => (streaming.stream)(&mut BufReader::new(File::open(path)?))?,
+        None => (streaming.stream)(&mut io::stdin().lock())?,
+    };
+    for answer in answers {
+        println!("{answer}");
+    }
+    Ok(())
 }
 
-pub fn get_input(day: u8, test: bool) -> String {
-    let mut input_file = "input";
-    if test {
-        input_file = "test_input";
+/// Runs the stress test of the days selected by the arguments, with the generators seeded by
+/// `--seed` (0 by default) and each day stopped after a size taking `--limit` seconds (1 by
+/// default).
+fn stress(mut arguments: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let mut days = vec![];
+    let mut seed = 0;
+    let mut limit = 1.0;
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or(format!("missing the value of {argument}"))
+        };
+        match argument.as_str() {
+            "--seed" => seed = value()?.parse()?,
+            "--limit" => limit = value()?.parse()?,
+            day => days.push(day.parse()?),
+        }
+    }
+    if days.is_empty() {
+        days = (1..=DAYS.len() as u8).collect();
+    }
+
+    let mut failed = false;
+    for (i, &day) in days.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("Day {day}");
+        if let Err(error) = stress::stress(day, seed, Duration::from_secs_f64(limit)) {
+            eprintln!("error: {error}");
+            failed = true;
+        }
+    }
+    match failed {
+        true => Err("some days failed".into()),
+        false => Ok(()),
     }
-    let path = format!("src/day{day:0>2}/{input_file}");
-    fs::read_to_string(path).expect("Should have been able to read the file.")
 }
@@ -0,0 +1,244 @@
+// Number-theory helpers shared by the puzzles.
+//
+// All calculations are done on `i128` (or `u128`), so the results for the narrower types cannot
+// overflow in the intermediate steps. Functions whose result may not fit in the type return an
+// `Option`.
+
+/// An integer type supported by the helpers in this module.
+pub trait Integer: Copy + Ord + Into<i128> + TryFrom<i128> {}
+
+impl Integer for i64 {}
+impl Integer for u64 {}
+impl Integer for i128 {}
+
+fn narrow<T: Integer>(value: i128) -> Option<T> {
+    T::try_from(value).ok()
+}
+
+/// Returns the non-negative greatest common divisor of `a` and `b`.
+///
+/// Panics if the result does not fit in `T`, which can only happen for `gcd(T::MIN, 0)` and
+/// `gcd(T::MIN, T::MIN)` of a signed type.
+pub fn gcd<T: Integer>(a: T, b: T) -> T {
+    i128::try_from(unsigned_gcd(
+        a.into().unsigned_abs(),
+        b.into().unsigned_abs(),
+    ))
+    .ok()
+    .and_then(narrow)
+    .expect("The greatest common divisor should fit in the type.")
+}
+
+fn unsigned_gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Returns the non-negative least common multiple of `a` and `b`, or `None` if it overflows.
+pub fn lcm<T: Integer>(a: T, b: T) -> Option<T> {
+    let (a, b) = (a.into(), b.into());
+    if a == 0 || b == 0 {
+        return narrow(0);
+    }
+
+    // The greatest common divisor is computed unsigned, as it does not fit in `i128` for
+    // `lcm(i128::MIN, i128::MIN)`.
+    let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+    let multiple = (a / unsigned_gcd(a, b)).checked_mul(b)?;
+    i128::try_from(multiple).ok().and_then(narrow)
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y = g`, where `g` is the non-negative greatest
+/// common divisor of `a` and `b`, or `None` if a step overflows `i128` (which can only happen
+/// with `i128::MIN`).
+pub fn extended_gcd<T: Integer>(a: T, b: T) -> Option<(i128, i128, i128)> {
+    let (mut old_r, mut r) = (a.into(), b.into());
+    let (mut old_x, mut x) = (1_i128, 0_i128);
+    let (mut old_y, mut y) = (0_i128, 1_i128);
+
+    // Returns `old - quotient * value`.
+    let next =
+        |old: i128, quotient: i128, value: i128| old.checked_sub(quotient.checked_mul(value)?);
+    while r != 0 {
+        let quotient = old_r.checked_div(r)?;
+        (old_r, r) = (r, next(old_r, quotient, r)?);
+        (old_x, x) = (x, next(old_x, quotient, x)?);
+        (old_y, y) = (y, next(old_y, quotient, y)?);
+    }
+
+    if old_r < 0 {
+        return Some((
+            old_r.checked_neg()?,
+            old_x.checked_neg()?,
+            old_y.checked_neg()?,
+        ));
+    }
+    Some((old_r, old_x, old_y))
+}
+
+/// Returns `(a * b) mod modulus` in `[0, modulus)` without overflowing.
+fn mul_mod(a: i128, b: i128, modulus: i128) -> i128 {
+    let (mut a, mut b) = (a.rem_euclid(modulus), b.rem_euclid(modulus));
+    if let Some(product) = a.checked_mul(b) {
+        return product % modulus;
+    }
+
+    // Only reachable for moduli that do not fit in 64 bits.
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, modulus);
+        }
+        a = add_mod(a, a, modulus);
+        b >>= 1;
+    }
+    result
+}
+
+fn add_mod(a: i128, b: i128, modulus: i128) -> i128 {
+    // Both values are in `[0, modulus)`, so `a - modulus + b` cannot overflow.
+    let difference = a - modulus;
+    let sum = difference + b;
+    if sum < 0 {
+        sum + modulus
+    } else {
+        sum
+    }
+}
+
+/// Returns `base ^ exponent mod modulus` in `[0, modulus)`.
+///
+/// Panics if `exponent` is negative or `modulus` is not positive.
+pub fn mod_pow<T: Integer>(base: T, exponent: T, modulus: T) -> T {
+    let (mut base, mut exponent, modulus) = (base.into(), exponent.into(), modulus.into());
+    assert!(exponent >= 0, "The exponent should not be negative.");
+    assert!(modulus > 0, "The modulus should be positive.");
+
+    let mut result = 1 % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+
+    narrow(result).expect("A value smaller than the modulus should fit in the type.")
+}
+
+/// Returns `x` in `[0, modulus)` such that `a * x mod modulus = 1`, or `None` if `a` and `modulus`
+/// are not coprime.
+///
+/// Panics if `modulus` is not positive.
+pub fn mod_inverse<T: Integer>(a: T, modulus: T) -> Option<T> {
+    let modulus = modulus.into();
+    assert!(modulus > 0, "The modulus should be positive.");
+
+    // Both values are in `[0, modulus]`, so the steps cannot overflow.
+    let (g, x, _) = extended_gcd(a.into().rem_euclid(modulus), modulus)?;
+    if g != 1 {
+        return None;
+    }
+
+    narrow(x.rem_euclid(modulus))
+}
+
+/// Solves the system of congruences `x = residue (mod modulus)` with the Chinese remainder
+/// theorem.
+///
+/// The moduli do not need to be coprime. Returns `(x, m)`, where `x` is the smallest non-negative
+/// solution and `m` is the least common multiple of the moduli, or `None` if the system has no
+/// solution or `m` does not fit in `T`.
+pub fn crt<T: Integer>(congruences: &[(T, T)]) -> Option<(T, T)> {
+    let (mut x, mut m) = (0_i128, 1_i128);
+
+    for &(residue, modulus) in congruences {
+        let (residue, modulus) = (residue.into(), modulus.into());
+        assert!(modulus > 0, "The modulus should be positive.");
+
+        // Find `k` such that `x + k * m = residue (mod modulus)`. Both `m` and `modulus` are
+        // positive, so the steps cannot overflow, and both `residue` and `x` are reduced to
+        // non-negative values before their difference.
+        let (g, inverse, _) = extended_gcd(m, modulus)?;
+        let difference = (residue.rem_euclid(modulus) - x).rem_euclid(modulus);
+        if difference % g != 0 {
+            return None;
+        }
+
+        let step = modulus / g;
+        let k = mul_mod(difference / g, inverse, step);
+        let new_m = m.checked_mul(step)?;
+        x = add_mod(x, mul_mod(k, m, new_m), new_m);
+        m = new_m;
+    }
+
+    Some((narrow(x)?, narrow(m)?))
+}
+
+#[test]
+fn test_gcd() {
+    assert_eq!(gcd(12_i64, 18), 6);
+    assert_eq!(gcd(-12_i64, 18), 6);
+    assert_eq!(gcd(0_u64, 7), 7);
+    assert_eq!(gcd(i128::MIN, 6), 2);
+
+    assert_eq!(lcm(4_i64, 6), Some(12));
+    assert_eq!(lcm(0_i64, 6), Some(0));
+    assert_eq!(lcm(u64::MAX, u64::MAX - 1), None);
+    assert_eq!(lcm(1_u64 << 32, 3 << 31), Some(3 << 32));
+    assert_eq!(lcm(i128::MIN, i128::MIN), None);
+    assert_eq!(lcm(i128::MIN, 1), None);
+    assert_eq!(lcm(i64::MIN, -1), None);
+    assert_eq!(lcm(i128::MAX, -1), Some(i128::MAX));
+
+    let (g, x, y) = extended_gcd(240_i64, 46).unwrap();
+    assert_eq!(g, 2);
+    assert_eq!(240 * x + 46 * y, 2);
+
+    // At the limits of `i128`, the steps either fit or the result is `None`.
+    assert_eq!(extended_gcd(i128::MIN, -1), None);
+    assert_eq!(extended_gcd(i128::MIN, 0), None);
+    assert_eq!(extended_gcd(i128::MAX, 0), Some((i128::MAX, 1, 0)));
+    let (g, x, y) = extended_gcd(i128::MAX, i128::MAX - 1).unwrap();
+    assert_eq!(g, 1);
+    assert_eq!(
+        i128::MAX
+            .checked_mul(x)
+            .and_then(|a| a.checked_add((i128::MAX - 1).checked_mul(y)?)),
+        Some(1)
+    );
+}
+
+#[test]
+fn test_modular_arithmetic() {
+    assert_eq!(mod_pow(2_i64, 10, 1000), 24);
+    assert_eq!(mod_pow(-2_i64, 3, 5), 2);
+    assert_eq!(mod_pow(u64::MAX - 1, u64::MAX, u64::MAX), u64::MAX - 1);
+    // Fermat's little theorem for the Mersenne prime 2^89 - 1.
+    let prime = (1_i128 << 89) - 1;
+    assert_eq!(mod_pow(3, prime - 1, prime), 1);
+
+    assert_eq!(mod_inverse(3_i64, 11), Some(4));
+    assert_eq!(mod_inverse(6_u64, 9), None);
+    assert_eq!(mod_inverse(i128::MIN, 3), Some(1));
+    assert_eq!(mod_inverse(-1, i128::MAX), Some(i128::MAX - 1));
+
+    assert_eq!(crt(&[(2_i64, 3), (3, 5), (2, 7)]), Some((23, 105)));
+    assert_eq!(crt(&[(1_u64, 4), (3, 6)]), Some((9, 12)));
+    assert_eq!(crt(&[(1_i64, 4), (2, 6)]), None);
+    assert_eq!(crt::<i64>(&[]), Some((0, 1)));
+
+    // The residues are reduced before any difference, so they can be at the limits of `i128`.
+    assert_eq!(crt(&[(1_i128, 3), (i128::MIN, 5)]), Some((7, 15)));
+    assert_eq!(
+        crt(&[(i128::MIN, i128::MAX)]),
+        Some((i128::MAX - 1, i128::MAX))
+    );
+    assert_eq!(crt(&[(0_i128, 2), (0, i128::MAX)]), None);
+    let (x, m) = crt(&[(i128::MAX, 1 << 125), (i128::MIN, 3)]).unwrap();
+    assert_eq!(m, 3 << 125);
+    assert_eq!(x % (1 << 125), i128::MAX % (1 << 125));
+    assert_eq!(x % 3, i128::MIN.rem_euclid(3));
+}
@@ -0,0 +1,292 @@
+// Recognition of the block letters drawn by the puzzles (e.g. the CRT screen from day 10).
+
+use std::fmt;
+
+/// The letters of the 4x6 font. Some letters (e.g. `I` and `Y`) have a different width.
+pub(crate) const SMALL_FONT: [(char, [&str; 6]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// The letters of the 6x10 font.
+const LARGE_FONT: [(char, [&str; 10]); 15] = [
+    (
+        'A',
+        [
+            "..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'B',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#",
+            "#....#", "#####.",
+        ],
+    ),
+    (
+        'C',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#....#", ".####.",
+        ],
+    ),
+    (
+        'E',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'F',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'G',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#",
+            "#...##", ".###.#",
+        ],
+    ),
+    (
+        'H',
+        [
+            "#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'J',
+        [
+            "...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.",
+            "#...#.", ".###..",
+        ],
+    ),
+    (
+        'K',
+        [
+            "#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..",
+            "#...#.", "#....#",
+        ],
+    ),
+    (
+        'L',
+        [
+            "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'N',
+        [
+            "#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##",
+            "#...##", "#....#",
+        ],
+    ),
+    (
+        'P',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'R',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'X',
+        [
+            "#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'Z',
+        [
+            "######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OcrError {
+    /// There is no font with this number of rows.
+    UnsupportedHeight(usize),
+    /// The rows of the bitmap have different lengths.
+    RaggedRows,
+    /// The glyph starting at this column does not match any letter. The glyph is drawn with `#`
+    /// and `.`, one row per line.
+    UnknownGlyph { column: usize, glyph: String },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::UnsupportedHeight(height) => {
+                write!(f, "there is no font with the height of {height} rows")
+            }
+            OcrError::RaggedRows => write!(f, "the rows of the bitmap have different lengths"),
+            OcrError::UnknownGlyph { column, glyph } => {
+                write!(f, "unknown glyph at column {column}:\n{glyph}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Draws the columns `start..end` of the bitmap with `#` and `.`, one row per line.
+fn draw<R: AsRef<[bool]>>(rows: &[R], start: usize, end: usize) -> String {
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            row.as_ref()[start..end]
+                .iter()
+                .map(|&pixel| if pixel { '#' } else { '.' })
+                .collect()
+        })
+        .collect();
+
+    lines.join("\n")
+}
+
+/// Draws a font glyph the same way as `draw`, without the empty columns on its sides.
+fn trim_glyph(glyph: &[&str]) -> String {
+    let rows: Vec<Vec<bool>> = glyph
+        .iter()
+        .map(|row| row.chars().map(|char| char == '#').collect())
+        .collect();
+    let is_empty = |column: &usize| rows.iter().all(|row| !row[*column]);
+
+    let width = rows[0].len();
+    let start = (0..width).find(|column| !is_empty(column)).unwrap_or(0);
+    let end = (0..width).rfind(|column| !is_empty(column)).unwrap_or(0) + 1;
+
+    draw(&rows, start, end)
+}
+
+/// Reads the letters from a bitmap, where `true` is a lit pixel.
+///
+/// The font is selected by the number of rows. Letters are separated by at least one empty
+/// column.
+pub fn recognize<R: AsRef<[bool]>>(rows: &[R]) -> Result<String, OcrError> {
+    let font: Vec<(char, String)> = match rows.len() {
+        6 => SMALL_FONT
+            .iter()
+            .map(|(letter, glyph)| (*letter, trim_glyph(glyph)))
+            .collect(),
+        10 => LARGE_FONT
+            .iter()
+            .map(|(letter, glyph)| (*letter, trim_glyph(glyph)))
+            .collect(),
+        height => return Err(OcrError::UnsupportedHeight(height)),
+    };
+
+    let width = rows[0].as_ref().len();
+    if rows.iter().any(|row| row.as_ref().len() != width) {
+        return Err(OcrError::RaggedRows);
+    }
+    let is_empty = |column: usize| rows.iter().all(|row| !row.as_ref()[column]);
+
+    let mut result = String::new();
+    let mut column = 0;
+    while column < width {
+        if is_empty(column) {
+            column += 1;
+            continue;
+        }
+
+        let start = column;
+        while column < width && !is_empty(column) {
+            column += 1;
+        }
+
+        let glyph = draw(rows, start, column);
+        match font.iter().find(|(_, font_glyph)| *font_glyph == glyph) {
+            Some((letter, _)) => result.push(*letter),
+            None => {
+                return Err(OcrError::UnknownGlyph {
+                    column: start,
+                    glyph,
+                })
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Parses a bitmap drawn with `#` and `.`, one row per line.
+#[cfg(test)]
+fn parse_bitmap(drawing: &str) -> Vec<Vec<bool>> {
+    drawing
+        .lines()
+        .map(|line| line.chars().map(|char| char == '#').collect())
+        .collect()
+}
+
+#[test]
+fn test_small_font() {
+    let bitmap = parse_bitmap(
+        ".##..###...##..####.####..##..#..#.###...##.#..#.#.....##..###..###...###.#..#.#...#.####
+#..#.#..#.#..#.#....#....#..#.#..#..#.....#.#.#..#....#..#.#..#.#..#.#....#..#.#...#....#
+#..#.###..#....###..###..#....####..#.....#.##...#....#..#.#..#.#..#.#....#..#..#.#....#.
+####.#..#.#....#....#....#.##.#..#..#.....#.#.#..#....#..#.###..###...##..#..#...#....#..
+#..#.#..#.#..#.#....#....#..#.#..#..#..#..#.#.#..#....#..#.#....#.#.....#.#..#...#...#...
+#..#.###...##..####.#.....###.#..#.###..##..#..#.####..##..#....#..#.###...##....#...####",
+    );
+    assert_eq!(recognize(&bitmap), Ok("ABCEFGHIJKLOPRSUYZ".to_string()));
+
+    let bitmap = parse_bitmap("#..#\n#..#\n####\n#..#\n#..#\n#.##");
+    assert_eq!(
+        recognize(&bitmap),
+        Err(OcrError::UnknownGlyph {
+            column: 0,
+            glyph: "#..#\n#..#\n####\n#..#\n#..#\n#.##".to_string()
+        })
+    );
+
+    assert_eq!(recognize(&[[true]]), Err(OcrError::UnsupportedHeight(1)));
+}
+
+#[test]
+fn test_large_font() {
+    let bitmap = parse_bitmap(
+        "#....#..######..#.....
+#....#..#.......#.....
+.#..#...#.......#.....
+.#..#...#.......#.....
+..##....#####...#.....
+..##....#.......#.....
+.#..#...#.......#.....
+.#..#...#.......#.....
+#....#..#.......#.....
+#....#..######..######",
+    );
+    assert_eq!(recognize(&bitmap), Ok("XEL".to_string()));
+}
@@ -0,0 +1,301 @@
+// Shared helpers for parsing the puzzle inputs.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// An error with the position of the problem in the input.
+///
+/// Both `line` and `column` are 1-based. Errors produced while parsing a single line have `line`
+/// set to 0 until they are attached to a line with `on_line` or `in_line`. The message says what
+/// was expected, and the text of the line is shown with the problem underlined when it is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    /// The number of underlined characters.
+    pub length: usize,
+    pub message: String,
+    pub text: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(column: usize, message: impl Into<String>) -> Self {
+        ParseError {
+            line: 0,
+            column,
+            length: 1,
+            message: message.into(),
+            text: None,
+        }
+    }
+
+    /// An error about the whole line.
+    pub fn whole_line(line: usize, text: &str, message: impl Into<String>) -> Self {
+        ParseError::new(1, message)
+            .with_length(text.chars().count())
+            .in_line(line, text)
+    }
+
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.length = length.max(1);
+        self
+    }
+
+    pub fn on_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    pub fn in_line(mut self, line: usize, text: &str) -> Self {
+        self.line = line;
+        self.text = Some(text.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )?;
+
+        if let Some(text) = &self.text {
+            // The column counts bytes, the underline is aligned with the characters.
+            let start = self.column.saturating_sub(1).min(text.len());
+            let indent = match text.get(..start) {
+                Some(prefix) => prefix.chars().count(),
+                None => start,
+            };
+            let margin = " ".repeat(self.line.to_string().len());
+            write!(
+                f,
+                "\n{} | {text}\n{margin} | {}{}",
+                self.line,
+                " ".repeat(indent),
+                "^".repeat(self.length),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A group of lines separated from the others by blank lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block<'a> {
+    /// The 1-based number of the first line of the block.
+    pub line: usize,
+    pub lines: Vec<&'a str>,
+}
+
+/// Matches `input` against `pattern`, where each `{}` is a field, and returns the fields with their
+/// 1-based columns.
+///
+/// A field ends where the literal text following it in the pattern starts, so two fields cannot
+/// be adjacent. Use the `scan!` macro to also convert the fields to the expected types.
+pub fn scan_fields<'a>(input: &'a str, pattern: &str) -> Result<Vec<(usize, &'a str)>, ParseError> {
+    let literals: Vec<&str> = pattern.split("{}").collect();
+    let mut fields = vec![];
+    let mut position = 0;
+
+    for (i, literal) in literals.iter().enumerate() {
+        if i > 0 {
+            let rest = &input[position..];
+            let end = if literal.is_empty() {
+                if i != literals.len() - 1 {
+                    panic!("Adjacent fields in the pattern `{pattern}`.");
+                }
+                rest.len()
+            } else {
+                rest.find(literal).ok_or_else(|| {
+                    ParseError::new(position + 1, format!("expected `{literal}` after `{rest}`"))
+                        .with_length(rest.chars().count())
+                })?
+            };
+            if end == 0 {
+                return Err(ParseError::new(position + 1, "expected a value"));
+            }
+            fields.push((position + 1, &rest[..end]));
+            position += end;
+        }
+
+        if !input[position..].starts_with(literal) {
+            return Err(ParseError::new(
+                position + 1,
+                format!("expected `{literal}`, found `{}`", &input[position..]),
+            )
+            .with_length(input[position..].chars().count()));
+        }
+        position += literal.len();
+    }
+
+    if position != input.len() {
+        return Err(ParseError::new(
+            position + 1,
+            format!(
+                "expected the end of the line, found `{}`",
+                &input[position..]
+            ),
+        )
+        .with_length(input[position..].chars().count()));
+    }
+
+    Ok(fields)
+}
+
+/// Converts a field returned by `scan_fields` to `T`.
+pub fn parse_field<T: FromStr>(field: Option<(usize, &str)>) -> Result<T, ParseError> {
+    let (column, text) = field.ok_or_else(|| ParseError::new(0, "missing field"))?;
+    text.parse().map_err(|_| {
+        ParseError::new(
+            column,
+            format!("expected {}, found `{text}`", std::any::type_name::<T>()),
+        )
+        .with_length(text.chars().count())
+    })
+}
+
+/// Matches a line against a pattern with `{}` placeholders and parses the fields into a tuple.
+///
+/// ```ignore
+/// let (count, from, to) = scan!(line, "move {} from {} to {}", usize, usize, usize)?;
+/// ```
+macro_rules! scan {
+    ($input:expr, $pattern:expr, $($type:ty),+ $(,)?) => {
+        $crate::parse::scan_fields($input, $pattern).and_then(|fields| {
+            let mut fields = fields.into_iter();
+            Ok(($($crate::parse::parse_field::<$type>(fields.next())?,)+))
+        })
+    };
+}
+
+pub(crate) use scan;
+
+/// Extracts all (optionally negative) integers from the input, ignoring everything else.
+pub fn signed_integers<T: FromStr>(input: &str) -> Result<Vec<T>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut numbers = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let negative = bytes[i] == b'-' && i + 1 < bytes.len() && bytes[i + 1].is_ascii_digit();
+        if !negative && !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        numbers.push(parse_field(Some((start + 1, &input[start..i])))?);
+    }
+
+    Ok(numbers)
+}
+
+/// Splits the input into blocks of lines separated by blank lines.
+pub fn blocks(input: &str) -> Vec<Block<'_>> {
+    let mut blocks = vec![];
+    let mut current: Option<Block> = None;
+
+    for (i, line) in input.lines().enumerate() {
+        if line.is_empty() {
+            blocks.extend(current.take());
+        } else {
+            current
+                .get_or_insert(Block {
+                    line: i + 1,
+                    lines: vec![],
+                })
+                .lines
+                .push(line);
+        }
+    }
+    blocks.extend(current);
+
+    blocks
+}
+
+/// Parses every line with `parser`, attaching the line to the errors.
+pub fn parse_lines_with<'a, T>(
+    input: &'a str,
+    parser: impl Fn(&'a str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parser(line).map_err(|error| error.in_line(i + 1, line)))
+        .collect()
+}
+
+/// Parses every line as `T`.
+pub fn parse_lines<T: FromStr>(input: &str) -> Result<Vec<T>, ParseError> {
+    parse_lines_with(input, |line| parse_field(Some((1, line))))
+}
+
+#[test]
+fn test_scan() {
+    let result = scan!(
+        "move 3 from 1 to 22",
+        "move {} from {} to {}",
+        u8,
+        usize,
+        i32
+    );
+    assert_eq!(result, Ok((3, 1, 22)));
+
+    let result = scan!("2-4,6-8", "{}-{},{}-{}", i32, i32, i32, i32);
+    assert_eq!(result, Ok((2, 4, 6, 8)));
+
+    let result = scan!("move x from 1 to 2", "move {} from {} to {}", u8, u8, u8);
+    assert_eq!(result.unwrap_err().column, 6);
+
+    let result = scan!("move 1 form 1 to 2", "move {} from {} to {}", u8, u8, u8);
+    assert!(result.is_err());
+
+    let result = parse_lines_with("1-2\n3_4", |line| scan!(line, "{}-{}", u8, u8));
+    let error = result.unwrap_err();
+    assert_eq!((error.line, error.column), (2, 1));
+    assert_eq!(
+        error.to_string(),
+        "line 2, column 1: expected `-` after `3_4`\n2 | 3_4\n  | ^^^"
+    );
+
+    let error = ParseError::new(6, "expected u8, found `x`").in_line(10, "move x from 1 to 2");
+    assert_eq!(
+        error.to_string(),
+        "line 10, column 6: expected u8, found `x`\n10 | move x from 1 to 2\n   |      ^"
+    );
+}
+
+#[test]
+fn test_helpers() {
+    let result = signed_integers::<i32>("Sensor at x=2, y=-18: beacon at x=-2, y=15");
+    assert_eq!(result, Ok(vec![2, -18, -2, 15]));
+
+    let result = signed_integers::<u8>("a - 300");
+    assert_eq!(result.unwrap_err().column, 5);
+
+    let result = blocks("a\nb\n\n\nc\n");
+    assert_eq!(
+        result,
+        vec![
+            Block {
+                line: 1,
+                lines: vec!["a", "b"]
+            },
+            Block {
+                line: 5,
+                lines: vec!["c"]
+            },
+        ]
+    );
+
+    assert_eq!(parse_lines::<i32>("1\n-2"), Ok(vec![1, -2]));
+    assert_eq!(parse_lines::<i32>("1\n\n").unwrap_err().line, 2);
+}
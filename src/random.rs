@@ -0,0 +1,50 @@
+// Pseudo-random numbers for the generated inputs and the randomized structures, the same for
+// the same seed.
+
+use std::ops::RangeInclusive;
+
+/// A xorshift generator, seeded through splitmix64 so that every seed (even 0) works.
+#[derive(Debug, Clone)]
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        let mut state = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Random((state ^ (state >> 31)).max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number from 0 to `bound - 1`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Returns a number from `start` to `end - 1`.
+    pub fn range(&mut self, start: i64, end: i64) -> i64 {
+        start + self.below((end - start) as u64) as i64
+    }
+
+    pub fn pick<T: Copy>(&mut self, values: &[T]) -> T {
+        values[self.below(values.len() as u64) as usize]
+    }
+
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            values.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+
+    /// Returns an ASCII character of the range.
+    pub fn letter(&mut self, letters: RangeInclusive<char>) -> char {
+        let (start, end) = (*letters.start() as i64, *letters.end() as i64);
+        self.range(start, end + 1) as u8 as char
+    }
+}
@@ -0,0 +1,351 @@
+// Every day with its inputs, so the parts can be run and measured the same way.
+//
+// A day is run in stages: its input is parsed once, then each part solves the parsed input. The
+// stages are handed to a `Measure`, which can run them as many times as it needs (e.g. the
+// benchmarks).
+
+use crate::*;
+use std::error::Error;
+use std::fs;
+use std::io::BufRead;
+
+/// Runs the stages of a day (`parse`, `part1` and `part2`). A stage that is skipped (e.g. by the
+/// filter of the benchmarks) is still run once, as the next stages need its result.
+pub trait Measure {
+    fn measure(&mut self, stage: &str, run: &mut dyn FnMut());
+}
+
+/// Runs every stage once.
+pub struct Once;
+
+impl Measure for Once {
+    fn measure(&mut self, _stage: &str, run: &mut dyn FnMut()) {
+        run();
+    }
+}
+
+/// An input of a day.
+pub struct Input {
+    pub name: String,
+    pub text: String,
+    /// Whether this is the example from the puzzle, which some days solve with other parameters.
+    pub test: bool,
+}
+
+impl Input {
+    pub fn new(name: impl Into<String>, text: impl Into<String>, test: bool) -> Self {
+        Input {
+            name: name.into(),
+            text: text.into(),
+            test,
+        }
+    }
+}
+
+/// Parses the input, then solves both parts and returns their answers.
+type Run = fn(&Input, &mut dyn Measure) -> Result<[String; 2], Box<dyn Error>>;
+
+pub struct Solution {
+    pub day: u8,
+    pub run: Run,
+}
+
+type Part<'a, P> = &'a dyn Fn(&P) -> Result<String, Box<dyn Error>>;
+
+/// Runs the stages of a day on the input.
+fn stages<'a, P, E: Into<Box<dyn Error>>>(
+    input: &'a str,
+    measure: &mut dyn Measure,
+    parse: impl Fn(&'a str) -> Result<P, E>,
+    parts: [Part<P>; 2],
+) -> Result<[String; 2], Box<dyn Error>> {
+    let mut parsed = None;
+    measure.measure("parse", &mut || parsed = Some(parse(input)));
+    let parsed = parsed.unwrap_or_else(|| parse(input)).map_err(Into::into)?;
+
+    let mut answers = [String::new(), String::new()];
+    for (i, part) in parts.into_iter().enumerate() {
+        let mut answer = None;
+        measure.measure(&format!("part{}", i + 1), &mut || {
+            answer = Some(part(&parsed))
+        });
+        answers[i] = answer.unwrap_or_else(|| part(&parsed))?;
+    }
+    Ok(answers)
+}
+
+pub const SOLUTIONS: [Solution; 15] = [
+    Solution {
+        day: 1,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day01::parse,
+                [&|elves| Ok(day01::solve1(elves).to_string()), &|elves| {
+                    Ok(day01::solve2(elves).to_string())
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 2,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day02::parse,
+                [&|rounds| Ok(day02::solve1(rounds).to_string()), &|rounds| {
+                    Ok(day02::solve2(rounds).to_string())
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 3,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day03::parse,
+                [
+                    &|rucksacks| Ok(day03::solve1(rucksacks)?.to_string()),
+                    &|rucksacks| Ok(day03::solve2(rucksacks)?.to_string()),
+                ],
+            )
+        },
+    },
+    Solution {
+        day: 4,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day04::parse,
+                [&|pairs| Ok(day04::solve1(pairs).to_string()), &|pairs| {
+                    Ok(day04::solve2(pairs).to_string())
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 5,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day05::parse,
+                [&|procedure| Ok(day05::solve1(procedure)?), &|procedure| {
+                    Ok(day05::solve2(procedure)?)
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 6,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day06::parse,
+                [
+                    &|datastream| Ok(day06::solve1(datastream)?.to_string()),
+                    &|datastream| Ok(day06::solve2(datastream)?.to_string()),
+                ],
+            )
+        },
+    },
+    Solution {
+        day: 7,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day07::parse,
+                [&|sizes| Ok(day07::solve1(sizes).to_string()), &|sizes| {
+                    Ok(day07::solve2(sizes).to_string())
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 8,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day08::parse,
+                [&|rows| Ok(day08::solve1(rows).to_string()), &|rows| {
+                    Ok(day08::solve2(rows).to_string())
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 9,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day09::parse,
+                [
+                    &|motions| Ok(day09::solve1(motions)?.to_string()),
+                    &|motions| Ok(day09::solve2(motions)?.to_string()),
+                ],
+            )
+        },
+    },
+    Solution {
+        day: 10,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day10::parse,
+                [
+                    &|instructions| Ok(day10::solve1(instructions).to_string()),
+                    &|instructions| day10::solve2(instructions),
+                ],
+            )
+        },
+    },
+    Solution {
+        day: 11,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day11::parse,
+                [&|notes| Ok(day11::solve1(notes)?.to_string()), &|notes| {
+                    Ok(day11::solve2(notes)?.to_string())
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 12,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day12::parse,
+                [&|nodes| Ok(day12::solve1(nodes)?.to_string()), &|nodes| {
+                    Ok(day12::solve2(nodes)?.to_string())
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 13,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day13::parse,
+                [
+                    &|blocks| Ok(day13::solve1(blocks)?.to_string()),
+                    &|blocks| Ok(day13::solve2(blocks).to_string()),
+                ],
+            )
+        },
+    },
+    Solution {
+        day: 14,
+        run: |input, measure| {
+            stages(
+                &input.text,
+                measure,
+                day14::parse,
+                [&|paths| Ok(day14::solve1(paths)?.to_string()), &|paths| {
+                    Ok(day14::solve2(paths)?.to_string())
+                }],
+            )
+        },
+    },
+    Solution {
+        day: 15,
+        run: |input, measure| {
+            // The example checks another line and a smaller area than the real input.
+            let (tested_line, max) = match input.test {
+                true => (10, 20),
+                false => (2_000_000, 4_000_000),
+            };
+            stages(
+                &input.text,
+                measure,
+                day15::parse,
+                [
+                    &|reports| Ok(day15::solve1(reports, tested_line)?.to_string()),
+                    &|reports| Ok(day15::solve2(reports, max)?.to_string()),
+                ],
+            )
+        },
+    },
+];
+
+/// Reads the input once, without keeping it in memory, and returns the answers of both parts.
+type Stream = fn(&mut dyn BufRead) -> Result<[String; 2], Box<dyn Error>>;
+
+/// A day that can read its input as a stream.
+pub struct Streaming {
+    pub day: u8,
+    pub stream: Stream,
+}
+
+pub const STREAMING: [Streaming; 3] = [
+    Streaming {
+        day: 1,
+        stream: |reader| {
+            let (first, second) = day01::stream(reader)?;
+            Ok([first.to_string(), second.to_string()])
+        },
+    },
+    Streaming {
+        day: 6,
+        stream: |reader| {
+            let (first, second) = day06::stream(reader)?;
+            Ok([first.to_string(), second.to_string()])
+        },
+    },
+    Streaming {
+        day: 9,
+        stream: |reader| {
+            let (first, second) = day09::stream(reader)?;
+            Ok([first.to_string(), second.to_string()])
+        },
+    },
+];
+
+/// Returns the inputs of a day found in its directory, the example first.
+pub fn inputs(day: u8) -> Vec<Input> {
+    [("test_input", true), ("input", false)]
+        .into_iter()
+        .filter_map(|(name, test)| {
+            let text = fs::read_to_string(format!("src/day{day:0>2}/{name}")).ok()?;
+            Some(Input::new(name, text, test))
+        })
+        .collect()
+}
+
+#[test]
+fn test_solutions() {
+    let input = &inputs(1)[0];
+    assert!(input.test);
+    let result = (SOLUTIONS[0].run)(input, &mut Once).unwrap();
+    assert_eq!(result, ["24000", "45000"]);
+
+    for (i, solution) in SOLUTIONS.iter().enumerate() {
+        assert_eq!(solution.day as usize, i + 1);
+        let input = inputs(solution.day).pop().unwrap();
+        assert!(!input.test);
+        assert!((solution.run)(&input, &mut Once).is_ok());
+    }
+
+    // The streams agree with the other solutions.
+    for streaming in &STREAMING {
+        let input = inputs(streaming.day).pop().unwrap();
+        let solution = &SOLUTIONS[streaming.day as usize - 1];
+        let result = (streaming.stream)(&mut input.text.as_bytes()).unwrap();
+        assert_eq!(result, (solution.run)(&input, &mut Once).unwrap());
+    }
+}
@@ -0,0 +1,251 @@
+// Export of grids as still images and animations.
+//
+// Rendering is disabled unless the `--render` flag is given. The simulations create a `Renderer`
+// with `renderer`, which writes their frames to a GIF file in the `render` directory.
+
+use crate::grid::Grid;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const OUTPUT_DIRECTORY: &str = "render";
+/// The delay between the frames of the animations in hundredths of a second.
+const FRAME_DELAY: u16 = 2;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub type Color = [u8; 3];
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// An RGB image with 8 bits per channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    /// Draws every cell of the grid as a `scale`x`scale` square with the color from `palette`.
+    pub fn from_grid<T>(grid: &Grid<T>, palette: impl Fn(&T) -> Color, scale: usize) -> Self {
+        let mut pixels = Vec::with_capacity(grid.width * grid.height * scale * scale);
+        for row in grid.rows() {
+            let colors: Vec<Color> = row.iter().map(&palette).collect();
+            for _ in 0..scale {
+                for color in &colors {
+                    pixels.extend(std::iter::repeat_n(*color, scale));
+                }
+            }
+        }
+
+        Image {
+            width: grid.width * scale,
+            height: grid.height * scale,
+            pixels,
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.pixels.concat()
+    }
+
+    pub fn write_ppm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height)?;
+        file.write_all(&self.bytes())?;
+        file.flush()
+    }
+
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer
+            .write_image_data(&self.bytes())
+            .map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    /// Writes the image in the format given by the extension of the path (`ppm` or `png`).
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ppm") => self.write_ppm(path),
+            Some("png") => self.write_png(path),
+            _ => Err(io::Error::other(format!(
+                "Unknown image format of {}.",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// An animated GIF. All frames need to have the same size.
+pub struct Animation {
+    encoder: gif::Encoder<BufWriter<File>>,
+    width: u16,
+    height: u16,
+}
+
+impl Animation {
+    pub fn create(path: impl AsRef<Path>, width: usize, height: usize) -> io::Result<Self> {
+        let too_large = || io::Error::other("The animation is too large for a GIF.");
+        let width = u16::try_from(width).map_err(|_| too_large())?;
+        let height = u16::try_from(height).map_err(|_| too_large())?;
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = gif::Encoder::new(file, width, height, &[]).map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+
+        Ok(Animation {
+            encoder,
+            width,
+            height,
+        })
+    }
+
+    /// Adds a frame with its own palette, so it can use at most 256 colors.
+    pub fn add_frame(&mut self, image: &Image) -> io::Result<()> {
+        if (image.width, image.height) != (self.width as usize, self.height as usize) {
+            return Err(io::Error::other(
+                "The frame should have the size of the animation.",
+            ));
+        }
+
+        let mut palette: Vec<Color> = vec![];
+        let mut indices = Vec::with_capacity(image.pixels.len());
+        for pixel in &image.pixels {
+            let index = match palette.iter().position(|color| color == pixel) {
+                Some(index) => index,
+                None => {
+                    palette.push(*pixel);
+                    palette.len() - 1
+                }
+            };
+            indices.push(
+                u8::try_from(index).map_err(|_| io::Error::other("Too many colors in a frame."))?,
+            );
+        }
+
+        let mut frame = gif::Frame::from_palette_pixels(
+            self.width,
+            self.height,
+            indices,
+            palette.concat(),
+            None,
+        );
+        frame.delay = FRAME_DELAY;
+        self.encoder.write_frame(&frame).map_err(io::Error::other)
+    }
+}
+
+/// Writes the states of a simulation as the frames of an animation.
+pub struct Renderer<T> {
+    animation: Animation,
+    still_path: PathBuf,
+    palette: fn(&T) -> Color,
+    scale: usize,
+    every: usize,
+    steps: usize,
+}
+
+impl<T> Renderer<T> {
+    /// Adds the grid as a frame if this step is one of the rendered ones. The grid is only built
+    /// when it is needed.
+    pub fn step(&mut self, grid: impl FnOnce() -> Grid<T>) {
+        if self.steps.is_multiple_of(self.every) {
+            let image = Image::from_grid(&grid(), self.palette, self.scale);
+            self.animation
+                .add_frame(&image)
+                .expect("Should have been able to write the frame.");
+        }
+        self.steps += 1;
+    }
+
+    /// Adds the final state as the last frame and saves it as a still image.
+    pub fn finish(mut self, grid: Grid<T>) {
+        let image = Image::from_grid(&grid, self.palette, self.scale);
+        self.animation
+            .add_frame(&image)
+            .expect("Should have been able to write the frame.");
+        image
+            .save(&self.still_path)
+            .expect("Should have been able to write the image.");
+    }
+}
+
+/// Creates a renderer writing to `render/{name}.gif` if rendering is enabled. The final state is
+/// also saved to `render/{name}.png`.
+///
+/// Only every `every`-th step is rendered, which keeps the long simulations manageable.
+pub fn renderer<T>(
+    name: &str,
+    (width, height): (usize, usize),
+    palette: fn(&T) -> Color,
+    scale: usize,
+    every: usize,
+) -> io::Result<Option<Renderer<T>>> {
+    if !is_enabled() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(OUTPUT_DIRECTORY)?;
+    let directory = Path::new(OUTPUT_DIRECTORY);
+    let animation = Animation::create(
+        directory.join(format!("{name}.gif")),
+        width * scale,
+        height * scale,
+    )?;
+
+    Ok(Some(Renderer {
+        animation,
+        still_path: directory.join(format!("{name}.png")),
+        palette,
+        scale,
+        every: every.max(1),
+        steps: 0,
+    }))
+}
+
+#[test]
+fn test_image() {
+    let grid = Grid::from_fn(2, 1, |x, _| x == 1);
+    let image = Image::from_grid(&grid, |&lit| if lit { [255; 3] } else { [0; 3] }, 2);
+    assert_eq!((image.width, image.height), (4, 2));
+    assert_eq!(image.bytes(), [[0; 6], [255; 6], [0; 6], [255; 6]].concat());
+
+    let directory = std::env::temp_dir().join("advent_of_code_render_test");
+    fs::create_dir_all(&directory).unwrap();
+
+    image.save(directory.join("image.ppm")).unwrap();
+    let ppm = fs::read(directory.join("image.ppm")).unwrap();
+    assert!(ppm.starts_with(b"P6\n4 2\n255\n"));
+    assert_eq!(ppm.len(), 11 + 4 * 2 * 3);
+
+    image.save(directory.join("image.png")).unwrap();
+    assert!(image.save(directory.join("image.bmp")).is_err());
+    let png = fs::read(directory.join("image.png")).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+
+    let mut animation = Animation::create(directory.join("animation.gif"), 4, 2).unwrap();
+    animation.add_frame(&image).unwrap();
+    assert!(animation
+        .add_frame(&Image::from_grid(&grid, |_| [0; 3], 1))
+        .is_err());
+    drop(animation);
+    let gif = fs::read(directory.join("animation.gif")).unwrap();
+    assert!(gif.starts_with(b"GIF89a"));
+}
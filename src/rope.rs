@@ -0,0 +1,280 @@
+// Sequences which are split, reversed and joined in logarithmic time, whatever the number of
+// values moved.
+
+use crate::random::Random;
+
+const NONE: usize = usize::MAX;
+
+/// A sequence of values stored in `Ropes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rope(usize);
+
+impl Rope {
+    pub const EMPTY: Rope = Rope(NONE);
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Rope::EMPTY
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    value: T,
+    /// A random priority, higher than the ones of the children.
+    priority: u64,
+    /// The number of values in the subtree.
+    size: usize,
+    /// Whether the subtree is read backwards. The children are only swapped (passing the flag on
+    /// to them) when a split or a join goes through the node.
+    reversed: bool,
+    left: usize,
+    right: usize,
+}
+
+/// Ropes whose values are all stored in one vector.
+///
+/// Every rope is a treap: a binary tree whose in-order traversal is the sequence, and which is
+/// also a heap of random priorities, so that its depth is logarithmic on average.
+#[derive(Debug, Clone)]
+pub struct Ropes<T> {
+    nodes: Vec<Node<T>>,
+    random: Random,
+}
+
+impl<T> Default for Ropes<T> {
+    fn default() -> Self {
+        Ropes::new()
+    }
+}
+
+impl<T> Ropes<T> {
+    pub fn new() -> Self {
+        Ropes {
+            nodes: vec![],
+            random: Random::new(0),
+        }
+    }
+
+    fn size(&self, node: usize) -> usize {
+        match node {
+            NONE => 0,
+            _ => self.nodes[node].size,
+        }
+    }
+
+    fn update(&mut self, node: usize) {
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        self.nodes[node].size = 1 + self.size(left) + self.size(right);
+    }
+
+    /// Swaps the children of a reversed node, and reverses them instead.
+    fn push_down(&mut self, node: usize) {
+        if !self.nodes[node].reversed {
+            return;
+        }
+        let current = &mut self.nodes[node];
+        current.reversed = false;
+        std::mem::swap(&mut current.left, &mut current.right);
+        let children = [current.left, current.right];
+        for child in children {
+            if child != NONE {
+                self.nodes[child].reversed ^= true;
+            }
+        }
+    }
+
+    fn join(&mut self, left: usize, right: usize) -> usize {
+        if left == NONE {
+            return right;
+        }
+        if right == NONE {
+            return left;
+        }
+
+        if self.nodes[left].priority > self.nodes[right].priority {
+            self.push_down(left);
+            let child = self.nodes[left].right;
+            self.nodes[left].right = self.join(child, right);
+            self.update(left);
+            left
+        } else {
+            self.push_down(right);
+            let child = self.nodes[right].left;
+            self.nodes[right].left = self.join(left, child);
+            self.update(right);
+            right
+        }
+    }
+
+    /// Splits the tree after its first `at` values.
+    fn split(&mut self, node: usize, at: usize) -> (usize, usize) {
+        if node == NONE {
+            return (NONE, NONE);
+        }
+
+        self.push_down(node);
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        let left_size = self.size(left);
+        if at <= left_size {
+            let (first, second) = self.split(left, at);
+            self.nodes[node].left = second;
+            self.update(node);
+            (first, node)
+        } else {
+            let (first, second) = self.split(right, at - left_size - 1);
+            self.nodes[node].right = first;
+            self.update(node);
+            (node, second)
+        }
+    }
+
+    pub fn len(&self, rope: Rope) -> usize {
+        self.size(rope.0)
+    }
+
+    pub fn push(&mut self, rope: &mut Rope, value: T) {
+        self.nodes.push(Node {
+            value,
+            priority: self.random.next_u64(),
+            size: 1,
+            reversed: false,
+            left: NONE,
+            right: NONE,
+        });
+        rope.0 = self.join(rope.0, self.nodes.len() - 1);
+    }
+
+    /// Moves the values of `other` to the end of `rope`.
+    pub fn append(&mut self, rope: &mut Rope, other: Rope) {
+        rope.0 = self.join(rope.0, other.0);
+    }
+
+    /// Keeps the first `at` values in `rope`, and returns the rope of the other ones.
+    pub fn split_off(&mut self, rope: &mut Rope, at: usize) -> Rope {
+        let length = self.len(*rope);
+        assert!(at <= length, "Split at {at}, after the end ({length}).");
+        let (first, second) = self.split(rope.0, at);
+        rope.0 = first;
+        Rope(second)
+    }
+
+    pub fn reverse(&mut self, rope: Rope) {
+        if rope.0 != NONE {
+            self.nodes[rope.0].reversed ^= true;
+        }
+    }
+
+    pub fn last(&self, rope: Rope) -> Option<&T> {
+        let mut node = rope.0;
+        let mut reversed = false;
+        let mut last = None;
+        while node != NONE {
+            let current = &self.nodes[node];
+            reversed ^= current.reversed;
+            last = Some(&current.value);
+            node = if reversed {
+                current.left
+            } else {
+                current.right
+            };
+        }
+        last
+    }
+
+    /// Collects the nodes of the subtree, in order.
+    fn collect(&self, node: usize, reversed: bool, nodes: &mut Vec<usize>) {
+        if node == NONE {
+            return;
+        }
+        let current = &self.nodes[node];
+        let reversed = reversed ^ current.reversed;
+        let (first, second) = match reversed {
+            false => (current.left, current.right),
+            true => (current.right, current.left),
+        };
+        self.collect(first, reversed, nodes);
+        nodes.push(node);
+        self.collect(second, reversed, nodes);
+    }
+
+    fn nodes(&self, rope: Rope) -> Vec<usize> {
+        let mut nodes = Vec::with_capacity(self.len(rope));
+        self.collect(rope.0, false, &mut nodes);
+        nodes
+    }
+
+    /// Returns the values of the rope, in order.
+    pub fn values(&self, rope: Rope) -> Vec<&T> {
+        let nodes = self.nodes(rope);
+        nodes
+            .into_iter()
+            .map(|node| &self.nodes[node].value)
+            .collect()
+    }
+
+    /// Replaces the values of the rope, in order, keeping its length.
+    pub fn set_values(&mut self, rope: Rope, values: impl IntoIterator<Item = T>) {
+        let nodes = self.nodes(rope);
+        for (node, value) in nodes.into_iter().zip(values) {
+            self.nodes[node].value = value;
+        }
+    }
+}
+
+#[test]
+fn test_ropes() {
+    let mut ropes = Ropes::new();
+    let mut rope = Rope::EMPTY;
+    for value in 0..10 {
+        ropes.push(&mut rope, value);
+    }
+    let mut top = ropes.split_off(&mut rope, 6);
+    ropes.reverse(top);
+    assert_eq!(ropes.values(top), [&9, &8, &7, &6]);
+    assert_eq!(ropes.last(top), Some(&6));
+
+    let middle = ropes.split_off(&mut top, 1);
+    ropes.reverse(middle);
+    ropes.append(&mut rope, middle);
+    ropes.append(&mut rope, top);
+    assert_eq!(ropes.len(rope), 10);
+    assert_eq!(ropes.values(rope), [&0, &1, &2, &3, &4, &5, &6, &7, &8, &9]);
+    assert_eq!(ropes.last(Rope::EMPTY), None);
+
+    let top = ropes.split_off(&mut rope, 7);
+    ropes.reverse(top);
+    ropes.set_values(top, [1, 2, 3]);
+    ropes.append(&mut rope, top);
+    assert_eq!(ropes.values(rope), [&0, &1, &2, &3, &4, &5, &6, &1, &2, &3]);
+
+    // Random moves between three ropes, and the same moves between three vectors.
+    let mut random = Random::new(1);
+    let mut ropes = Ropes::new();
+    let mut stacks = [Rope::EMPTY; 3];
+    let mut vectors: Vec<Vec<u64>> = vec![vec![]; 3];
+    for _ in 0..2000 {
+        let (from, to) = (random.below(3) as usize, random.below(3) as usize);
+        if random.below(4) == 0 {
+            let value = random.next_u64();
+            ropes.push(&mut stacks[from], value);
+            vectors[from].push(value);
+            continue;
+        }
+
+        let at = random.below(vectors[from].len() as u64 + 1) as usize;
+        let rope = ropes.split_off(&mut stacks[from], at);
+        let mut moved = vectors[from].split_off(at);
+        if random.below(2) == 0 {
+            ropes.reverse(rope);
+            moved.reverse();
+        }
+        ropes.append(&mut stacks[to], rope);
+        vectors[to].extend(moved);
+    }
+    for (rope, vector) in stacks.into_iter().zip(&vectors) {
+        assert_eq!(ropes.values(rope), vector.iter().collect::<Vec<_>>());
+        assert_eq!(ropes.last(rope), vector.last());
+    }
+}
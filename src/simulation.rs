@@ -0,0 +1,136 @@
+// Simulations advanced one step at a time, and drawn at every step when rendering is enabled.
+
+use crate::grid::Grid;
+use crate::render::Renderer;
+use crate::terminal::Animation;
+use crate::trace::Recorder;
+
+pub trait Simulation {
+    fn step(&mut self);
+    fn is_done(&self) -> bool;
+}
+
+/// A simulation which can be drawn as a grid.
+pub trait Render: Simulation {
+    type Cell;
+
+    fn grid(&self) -> Grid<Self::Cell>;
+
+    /// The cell which should stay visible in the terminal.
+    fn focus(&self) -> (usize, usize) {
+        (0, 0)
+    }
+}
+
+/// Where the frames of a rendered simulation go. All outputs are optional.
+pub struct Frames<T> {
+    renderer: Option<Renderer<T>>,
+    animation: Option<Animation<T>>,
+    recorder: Option<Recorder<T>>,
+}
+
+impl<T> Frames<T> {
+    pub fn new(
+        renderer: Option<Renderer<T>>,
+        animation: Option<Animation<T>>,
+        recorder: Option<Recorder<T>>,
+    ) -> Self {
+        Frames {
+            renderer,
+            animation,
+            recorder,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.renderer.is_some() || self.animation.is_some() || self.recorder.is_some()
+    }
+
+    /// Draws the grid returned by `grid`, which is only built when needed.
+    pub fn frame(&mut self, focus: (usize, usize), grid: impl Fn() -> Grid<T>) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.step(&grid);
+        }
+        if let Some(animation) = &mut self.animation {
+            animation.frame(focus, &grid);
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&grid());
+        }
+    }
+
+    /// Saves the final state.
+    pub fn finish(self, grid: impl FnOnce() -> Grid<T>) {
+        if let Some(renderer) = self.renderer {
+            renderer.finish(grid());
+        }
+    }
+}
+
+/// Runs the simulation until it is done and returns the number of steps.
+pub fn run<S: Simulation>(simulation: &mut S) -> usize {
+    run_steps(simulation, usize::MAX)
+}
+
+/// Runs at most `steps` steps, stopping early if the simulation is done. Returns the number of
+/// steps.
+pub fn run_steps<S: Simulation>(simulation: &mut S, steps: usize) -> usize {
+    let mut done = 0;
+    while done < steps && !simulation.is_done() {
+        simulation.step();
+        done += 1;
+    }
+    done
+}
+
+/// Runs the simulation until it is done, drawing the initial state and every step. The final
+/// state is drawn with `final_grid`, which can differ from the intermediate ones.
+pub fn run_rendered<S: Render>(
+    simulation: &mut S,
+    mut frames: Frames<S::Cell>,
+    final_grid: impl FnOnce(&S) -> Grid<S::Cell>,
+) -> usize {
+    if frames.is_active() {
+        frames.frame(simulation.focus(), || simulation.grid());
+    }
+    let mut steps = 0;
+    while !simulation.is_done() {
+        simulation.step();
+        steps += 1;
+        if frames.is_active() {
+            frames.frame(simulation.focus(), || simulation.grid());
+        }
+    }
+    frames.finish(|| final_grid(simulation));
+    steps
+}
+
+#[cfg(test)]
+struct Sequence {
+    value: u64,
+    last: u64,
+}
+
+#[cfg(test)]
+impl Simulation for Sequence {
+    fn step(&mut self) {
+        self.value = (self.value * self.value + 1) % 255;
+    }
+
+    fn is_done(&self) -> bool {
+        self.value == self.last
+    }
+}
+
+#[test]
+fn test_simulation() {
+    // 3, 10, 101, 2, 5, 26, ...
+    let mut sequence = Sequence { value: 3, last: 5 };
+    assert_eq!(run_steps(&mut sequence, 2), 2);
+    assert_eq!(sequence.value, 101);
+    assert_eq!(run_steps(&mut sequence, 10), 2);
+    assert_eq!(run(&mut sequence), 0);
+
+    let mut sequence = Sequence { value: 3, last: 26 };
+    assert_eq!(run(&mut sequence), 5);
+}
@@ -0,0 +1,84 @@
+// Reading the inputs from any `BufRead` (a file, the standard input), without keeping them in
+// memory.
+
+use crate::parse::ParseError;
+use std::fmt;
+use std::io::{self, BufRead};
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "could not read the input: {error}"),
+            StreamError::Parse(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> Self {
+        StreamError::Io(error)
+    }
+}
+
+impl From<ParseError> for StreamError {
+    fn from(error: ParseError) -> Self {
+        StreamError::Parse(error)
+    }
+}
+
+/// Calls `f` with the 1-based number and the text of every line, without the line ending (`\n`
+/// or `\r\n`, like `str::lines`).
+///
+/// The same buffer is reused for every line, so only the longest line is kept in memory.
+pub fn for_each_line(
+    mut reader: impl BufRead,
+    mut f: impl FnMut(usize, &str) -> Result<(), ParseError>,
+) -> Result<(), StreamError> {
+    let mut line = String::new();
+    let mut number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+
+        let text = line.strip_suffix('\n').unwrap_or(&line);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        f(number, text)?;
+    }
+}
+
+#[test]
+fn test_for_each_line() {
+    let mut lines = vec![];
+    for_each_line("a\r\n\nbc\nd".as_bytes(), |number, line| {
+        lines.push((number, line.to_string()));
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(
+        lines,
+        [(1, "a"), (2, ""), (3, "bc"), (4, "d")].map(|(number, line)| (number, line.to_string()))
+    );
+
+    let result = for_each_line("1\n2\n".as_bytes(), |number, line| match line {
+        "2" => Err(ParseError::whole_line(number, line, "expected 1")),
+        _ => Ok(()),
+    });
+    let Err(StreamError::Parse(error)) = result else {
+        panic!("Expected a parse error.");
+    };
+    assert_eq!((error.line, error.column), (2, 1));
+
+    let result = for_each_line(&[b'a', 0xff, b'\n'][..], |_, _| Ok(()));
+    assert!(matches!(result, Err(StreamError::Io(_))));
+}
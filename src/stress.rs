@@ -0,0 +1,92 @@
+// Runs the solutions on generated inputs of growing sizes, to see how their running time grows.
+
+use crate::generate::GENERATORS;
+use crate::random::Random;
+use crate::registry::{Input, Measure, SOLUTIONS};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// The short stages are repeated for at least this long, and their mean time is reported.
+const MIN_DURATION: Duration = Duration::from_millis(20);
+/// The most sizes tried for a day, each twice the previous one.
+const MAX_STEPS: usize = 12;
+
+/// Measures the mean time of every stage.
+struct Timer(Vec<Duration>);
+
+impl Measure for Timer {
+    fn measure(&mut self, _stage: &str, run: &mut dyn FnMut()) {
+        let start = Instant::now();
+        let mut runs = 0;
+        while runs == 0 || start.elapsed() < MIN_DURATION {
+            run();
+            runs += 1;
+        }
+        self.0.push(start.elapsed() / runs);
+    }
+}
+
+/// Returns the exponent `k` for which the time grows like `size^k` between the two runs.
+fn growth((size1, time1): (usize, Duration), (size2, time2): (usize, Duration)) -> f64 {
+    let times = time2.as_secs_f64() / time1.as_secs_f64().max(f64::MIN_POSITIVE);
+    times.ln() / (size2 as f64 / size1 as f64).ln()
+}
+
+/// Runs the day on generated inputs of doubling sizes, until the largest size it accepts or
+/// until the stages of one size take longer than `limit` in total. Prints the time of every
+/// stage for every size, then how fast each of them grows with the size.
+pub fn stress(day: u8, seed: u64, limit: Duration) -> Result<(), Box<dyn Error>> {
+    let generator = GENERATORS
+        .iter()
+        .find(|generator| generator.day == day)
+        .ok_or_else(|| format!("there is no generator for day {day}"))?;
+    let solution = &SOLUTIONS[day as usize - 1];
+
+    println!(
+        "{:>10} {:>12} {:>12} {:>12} {:>12}",
+        "size", "bytes", "parse", "part1", "part2"
+    );
+    let mut runs: Vec<(usize, Vec<Duration>)> = vec![];
+    let mut size = *generator.sizes.start();
+    for _ in 0..MAX_STEPS {
+        let text = (generator.generate)(size, &mut Random::new(seed));
+        let input = Input::new(size.to_string(), text, false);
+        let mut timer = Timer(vec![]);
+        let result = (solution.run)(&input, &mut timer);
+
+        print!("{size:>10} {:>12}", input.text.len());
+        for time in &timer.0 {
+            print!(" {:>12}", format!("{time:.1?}"));
+        }
+        println!();
+        result.map_err(|error| format!("size {size}: {error}"))?;
+
+        let total: Duration = timer.0.iter().sum();
+        runs.push((size, timer.0));
+        if total > limit || size == *generator.sizes.end() {
+            break;
+        }
+        size = size.saturating_mul(2).min(*generator.sizes.end());
+    }
+
+    if let [(size1, first), .., (size2, last)] = &runs[..] {
+        let exponents: Vec<String> = ["parse", "part1", "part2"]
+            .iter()
+            .enumerate()
+            .map(|(i, stage)| {
+                let exponent = growth((*size1, first[i]), (*size2, last[i]));
+                format!("{stage} ~ size^{exponent:.1}")
+            })
+            .collect();
+        println!("growth: {}", exponents.join(", "));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_growth() {
+    let time = Duration::from_millis;
+    assert_eq!(growth((10, time(5)), (20, time(10))), 1.0);
+    assert_eq!(growth((10, time(5)), (40, time(80))), 2.0);
+    assert_eq!(growth((10, time(5)), (20, time(5))), 0.0);
+}
@@ -0,0 +1,291 @@
+// Live animation of the simulations in the terminal.
+//
+// Animations are disabled unless the `--animate` flag is given. Every frame is redrawn in place
+// with ANSI escape codes. While an animation is running, the following keys are supported:
+// `space` pauses and resumes, `n` shows the next frame while paused, `+` and `-` change the frame
+// rate, and `q` skips the rest of the animation.
+
+use crate::grid::Grid;
+use crate::render::Color;
+use std::fmt::Write as _;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SIZE: (usize, usize) = (80, 24);
+const MAX_FPS: u32 = 1000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static FPS: AtomicU32 = AtomicU32::new(30);
+static KEYS: OnceLock<Mutex<Receiver<u8>>> = OnceLock::new();
+
+pub fn enable(fps: u32) {
+    ENABLED.store(true, Ordering::Relaxed);
+    FPS.store(fps.clamp(1, MAX_FPS), Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The part of the grid shown in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    /// Scrolls the viewport, so `focus` stays at least a quarter of the viewport away from its
+    /// edges, without showing anything outside the grid.
+    pub fn follow(&mut self, focus: (usize, usize), grid_size: (usize, usize)) {
+        self.x = follow_axis(self.x, self.width, focus.0, grid_size.0);
+        self.y = follow_axis(self.y, self.height, focus.1, grid_size.1);
+    }
+}
+
+fn follow_axis(start: usize, length: usize, focus: usize, total: usize) -> usize {
+    let margin = length / 4;
+    let mut start = start;
+    if focus < start + margin {
+        start = focus.saturating_sub(margin);
+    } else if focus + margin >= start + length {
+        start = focus + margin + 1 - length;
+    }
+
+    start.min(total.saturating_sub(length))
+}
+
+/// Returns the number of columns and rows of the terminal.
+fn terminal_size() -> (usize, usize) {
+    let output = Command::new("stty")
+        .arg("size")
+        .stdin(Stdio::inherit())
+        .output();
+    let Ok(output) = output else {
+        return DEFAULT_SIZE;
+    };
+
+    let size = String::from_utf8_lossy(&output.stdout);
+    let mut size = size.split_whitespace().map(|value| value.parse::<usize>());
+    match (size.next(), size.next()) {
+        (Some(Ok(rows)), Some(Ok(columns))) if rows > 0 && columns > 0 => (columns, rows),
+        _ => DEFAULT_SIZE,
+    }
+}
+
+/// Switches the terminal between reading single keys without echo and reading whole lines.
+fn set_key_mode(enabled: bool) {
+    let arguments: &[&str] = if enabled {
+        &["-icanon", "-echo", "min", "1"]
+    } else {
+        &["icanon", "echo"]
+    };
+    // Without `stty` the keys are only read after `enter`, which is still usable.
+    let _ = Command::new("stty")
+        .args(arguments)
+        .stdin(Stdio::inherit())
+        .status();
+}
+
+/// Returns the keys pressed since the last call. The keys are read by a background thread.
+fn keys() -> &'static Mutex<Receiver<u8>> {
+    KEYS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut key = [0];
+            while let Ok(1) = io::stdin().read(&mut key) {
+                if sender.send(key[0]).is_err() {
+                    break;
+                }
+            }
+        });
+        Mutex::new(receiver)
+    })
+}
+
+/// Draws the states of a simulation in the terminal.
+pub struct Animation<T> {
+    title: String,
+    symbol: fn(&T) -> char,
+    palette: fn(&T) -> Color,
+    viewport: Option<Viewport>,
+    fps: u32,
+    paused: bool,
+    skipped: bool,
+    interactive: bool,
+    steps: usize,
+    next_frame: Instant,
+}
+
+impl<T> Animation<T> {
+    /// Draws the grid returned by `grid` (which is only built when needed), with the viewport
+    /// following `focus`, and waits for the next frame.
+    pub fn frame(&mut self, focus: (usize, usize), grid: impl FnOnce() -> Grid<T>) {
+        self.steps += 1;
+        if self.skipped {
+            return;
+        }
+
+        let grid = grid();
+        let viewport = self.viewport.get_or_insert_with(|| {
+            let (columns, rows) = terminal_size();
+            Viewport {
+                x: 0,
+                y: 0,
+                // Leave space for the status line.
+                width: columns.min(grid.width),
+                height: rows.saturating_sub(2).max(1).min(grid.height),
+            }
+        });
+        viewport.follow(focus, (grid.width, grid.height));
+        let viewport = *viewport;
+        self.draw(&grid, viewport);
+
+        self.wait();
+    }
+
+    fn draw(&self, grid: &Grid<T>, viewport: Viewport) {
+        // Move the cursor to the top left corner instead of clearing, which would flicker.
+        let mut output = String::from("\x1b[H");
+        for row in grid.rows().skip(viewport.y).take(viewport.height) {
+            let mut last_color = None;
+            for cell in &row[viewport.x..viewport.x + viewport.width] {
+                let color = (self.palette)(cell);
+                if last_color != Some(color) {
+                    let [r, g, b] = color;
+                    let _ = write!(output, "\x1b[38;2;{r};{g};{b}m");
+                    last_color = Some(color);
+                }
+                output.push((self.symbol)(cell));
+            }
+            output.push_str("\x1b[0m\x1b[K\n");
+        }
+
+        let state = if self.paused { "paused" } else { "running" };
+        let _ = write!(
+            output,
+            "{} | step {} | {} fps | {state} | space: pause, n: next, +/-: speed, q: skip\x1b[K",
+            self.title, self.steps, self.fps,
+        );
+
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(output.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    /// Handles the pressed keys and waits until the next frame should be drawn.
+    fn wait(&mut self) {
+        if !self.interactive {
+            thread::sleep(Duration::from_secs(1) / self.fps);
+            return;
+        }
+
+        let keys = keys().lock().unwrap();
+        self.next_frame += Duration::from_secs(1) / self.fps;
+        loop {
+            let timeout = if self.paused {
+                Duration::from_secs(3600)
+            } else {
+                self.next_frame.saturating_duration_since(Instant::now())
+            };
+
+            match keys.recv_timeout(timeout) {
+                Ok(b' ') => {
+                    self.paused = !self.paused;
+                    self.next_frame = Instant::now();
+                }
+                Ok(b'n') if self.paused => return,
+                Ok(b'+') => self.fps = (self.fps * 2).min(MAX_FPS),
+                Ok(b'-') => self.fps = (self.fps / 2).max(1),
+                Ok(b'q') => {
+                    self.skipped = true;
+                    return;
+                }
+                Ok(_) => (),
+                Err(RecvTimeoutError::Timeout) if !self.paused => return,
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.interactive = false;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl<T> Drop for Animation<T> {
+    fn drop(&mut self) {
+        if self.interactive {
+            set_key_mode(false);
+        }
+        // Show the cursor again.
+        let _ = writeln!(io::stdout(), "\x1b[?25h");
+    }
+}
+
+/// Creates an animation if animations are enabled.
+///
+/// `symbol` and `palette` return the character and its color for a cell.
+pub fn animation<T>(
+    title: &str,
+    symbol: fn(&T) -> char,
+    palette: fn(&T) -> Color,
+) -> Option<Animation<T>> {
+    if !is_enabled() {
+        return None;
+    }
+
+    let interactive = io::stdin().is_terminal();
+    if interactive {
+        set_key_mode(true);
+    }
+    // Clear the screen and hide the cursor.
+    print!("\x1b[2J\x1b[?25l");
+
+    Some(Animation {
+        title: title.to_string(),
+        symbol,
+        palette,
+        viewport: None,
+        fps: FPS.load(Ordering::Relaxed),
+        paused: false,
+        skipped: false,
+        interactive,
+        steps: 0,
+        next_frame: Instant::now(),
+    })
+}
+
+#[test]
+fn test_viewport() {
+    let mut viewport = Viewport {
+        x: 0,
+        y: 0,
+        width: 20,
+        height: 8,
+    };
+
+    viewport.follow((10, 4), (100, 50));
+    assert_eq!((viewport.x, viewport.y), (0, 0));
+
+    // The focus is kept 5 columns and 2 rows from the edges.
+    viewport.follow((16, 6), (100, 50));
+    assert_eq!((viewport.x, viewport.y), (2, 1));
+
+    viewport.follow((3, 1), (100, 50));
+    assert_eq!((viewport.x, viewport.y), (0, 0));
+
+    // The viewport does not leave the grid.
+    viewport.follow((99, 49), (100, 50));
+    assert_eq!((viewport.x, viewport.y), (80, 42));
+
+    viewport.follow((50, 20), (10, 5));
+    assert_eq!((viewport.x, viewport.y), (0, 0));
+}
@@ -0,0 +1,395 @@
+// Recording of the states of the simulations to trace files, and their interactive replay.
+//
+// Recording is disabled unless the `--trace` flag is given. Every state is written to
+// `trace/{name}.trace` as a text grid, usually as the cells that changed since the previous state.
+// A trace file starts with `trace`, followed by these lines:
+//
+// - `color C RRGGBB`: the color of the cells drawn with `C`, used to render the states.
+// - `key WIDTH HEIGHT`: a full state, followed by its rows.
+// - `diff X,Y:C X,Y:C ...`: the cells which changed since the previous state.
+
+use crate::grid::Grid;
+use crate::render::{Color, Image};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const OUTPUT_DIRECTORY: &str = "trace";
+/// The number of states between two full states, which limits the work to reach any state.
+const KEY_INTERVAL: usize = 1000;
+const UNKNOWN_COLOR: Color = [255, 0, 255];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Writes the states of a simulation to a trace file.
+pub struct Recorder<T> {
+    file: BufWriter<File>,
+    symbol: fn(&T) -> char,
+    palette: fn(&T) -> Color,
+    colors: HashMap<char, Color>,
+    previous: Option<Grid<char>>,
+    steps: usize,
+}
+
+impl<T> Recorder<T> {
+    pub fn record(&mut self, grid: &Grid<T>) {
+        self.write(grid)
+            .expect("Should have been able to write the trace.");
+    }
+
+    fn write(&mut self, grid: &Grid<T>) -> io::Result<()> {
+        let symbols = grid.map(self.symbol);
+        let changes = match &self.previous {
+            Some(previous)
+                if (previous.width, previous.height) == (symbols.width, symbols.height)
+                    && !self.steps.is_multiple_of(KEY_INTERVAL) =>
+            {
+                let mut changes = vec![];
+                for y in 0..symbols.height {
+                    for x in 0..symbols.width {
+                        if symbols.get(x, y) != previous.get(x, y) {
+                            changes.push((x, y));
+                        }
+                    }
+                }
+                Some(changes)
+            }
+            _ => None,
+        };
+
+        // Only the cells written to the file can have new symbols.
+        let written: Box<dyn Iterator<Item = (usize, usize)>> = match &changes {
+            Some(changes) => Box::new(changes.iter().copied()),
+            None => {
+                Box::new((0..symbols.height).flat_map(|y| (0..symbols.width).map(move |x| (x, y))))
+            }
+        };
+        for (x, y) in written {
+            let symbol = *symbols.get(x, y);
+            if !self.colors.contains_key(&symbol) {
+                let [r, g, b] = (self.palette)(grid.get(x, y));
+                writeln!(self.file, "color {symbol} {r:02x}{g:02x}{b:02x}")?;
+                self.colors.insert(symbol, [r, g, b]);
+            }
+        }
+
+        match changes {
+            Some(changes) => {
+                write!(self.file, "diff")?;
+                for (x, y) in changes {
+                    write!(self.file, " {x},{y}:{}", symbols.get(x, y))?;
+                }
+                writeln!(self.file)?;
+            }
+            None => {
+                writeln!(self.file, "key {} {}", symbols.width, symbols.height)?;
+                for row in symbols.rows() {
+                    writeln!(self.file, "{}", row.iter().collect::<String>())?;
+                }
+            }
+        }
+
+        self.previous = Some(symbols);
+        self.steps += 1;
+        Ok(())
+    }
+}
+
+impl<T> Drop for Recorder<T> {
+    fn drop(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+/// Creates a recorder writing to `trace/{name}.trace` if recording is enabled.
+///
+/// `symbol` and `palette` return the character and its color for a cell.
+pub fn recorder<T>(
+    name: &str,
+    symbol: fn(&T) -> char,
+    palette: fn(&T) -> Color,
+) -> Option<Recorder<T>> {
+    if !is_enabled() {
+        return None;
+    }
+
+    fs::create_dir_all(OUTPUT_DIRECTORY).expect("Should have been able to create the directory.");
+    let path = Path::new(OUTPUT_DIRECTORY).join(format!("{name}.trace"));
+    let mut file =
+        BufWriter::new(File::create(path).expect("Should have been able to create the trace."));
+    writeln!(file, "trace").expect("Should have been able to write the trace.");
+
+    Some(Recorder {
+        file,
+        symbol,
+        palette,
+        colors: HashMap::new(),
+        previous: None,
+        steps: 0,
+    })
+}
+
+enum Step {
+    Key(Grid<char>),
+    Diff(Vec<(usize, usize, char)>),
+}
+
+/// The states of a simulation read from a trace file.
+pub struct Trace {
+    colors: HashMap<char, Color>,
+    steps: Vec<Step>,
+}
+
+fn invalid(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {message}", line + 1),
+    )
+}
+
+/// Parses the changes of a `diff` line. A changed cell can itself be a space, so the changes are
+/// read one at a time instead of split at the spaces.
+fn parse_changes(changes: &str) -> Option<Vec<(usize, usize, char)>> {
+    let mut result = vec![];
+    let mut rest = changes;
+    while !rest.is_empty() {
+        let (x, after) = rest.split_once(',')?;
+        let (y, after) = after.split_once(':')?;
+        let mut symbol = after.chars();
+        result.push((x.parse().ok()?, y.parse().ok()?, symbol.next()?));
+
+        rest = symbol.as_str();
+        if !rest.is_empty() {
+            rest = rest.strip_prefix(' ')?;
+        }
+    }
+    Some(result)
+}
+
+impl Trace {
+    pub fn parse(reader: impl BufRead) -> io::Result<Self> {
+        let lines: Vec<String> = reader.lines().collect::<io::Result<_>>()?;
+        if lines.first().map(String::as_str) != Some("trace") {
+            return Err(invalid(0, "expected `trace`"));
+        }
+
+        let mut trace = Trace {
+            colors: HashMap::new(),
+            steps: vec![],
+        };
+        let mut size = None;
+        let mut index = 1;
+        while index < lines.len() {
+            let line = &lines[index];
+            let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match kind {
+                "color" => {
+                    let mut symbol = rest.chars();
+                    let (Some(character), Some(' ')) = (symbol.next(), symbol.next()) else {
+                        return Err(invalid(index, "expected `color C RRGGBB`"));
+                    };
+                    let color = u32::from_str_radix(symbol.as_str(), 16)
+                        .map_err(|_| invalid(index, "expected a color as `RRGGBB`"))?;
+                    let [_, r, g, b] = color.to_be_bytes();
+                    trace.colors.insert(character, [r, g, b]);
+                }
+                "key" => {
+                    let dimensions: Vec<usize> = rest
+                        .split(' ')
+                        .map(|value| value.parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid(index, "expected `key WIDTH HEIGHT`"))?;
+                    let [width, height] = dimensions[..] else {
+                        return Err(invalid(index, "expected `key WIDTH HEIGHT`"));
+                    };
+                    let Some(rows) = lines.get(index + 1..index + 1 + height) else {
+                        return Err(invalid(index, "expected the rows of the state"));
+                    };
+                    let rows: Vec<Vec<char>> =
+                        rows.iter().map(|row| row.chars().collect()).collect();
+                    if let Some(row) = rows.iter().position(|row| row.len() != width) {
+                        return Err(invalid(index + 1 + row, "expected a row of the state"));
+                    }
+                    trace
+                        .steps
+                        .push(Step::Key(Grid::from_fn(width, height, |x, y| rows[y][x])));
+                    size = Some((width, height));
+                    index += height;
+                }
+                "diff" => {
+                    let Some((width, height)) = size else {
+                        return Err(invalid(index, "expected a full state first"));
+                    };
+                    match parse_changes(rest) {
+                        Some(changes)
+                            if changes.iter().all(|&(x, y, _)| x < width && y < height) =>
+                        {
+                            trace.steps.push(Step::Diff(changes))
+                        }
+                        _ => return Err(invalid(index, "expected `X,Y:C` inside the state")),
+                    }
+                }
+                _ => return Err(invalid(index, "expected `color`, `key` or `diff`")),
+            }
+            index += 1;
+        }
+
+        Ok(trace)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Trace::parse(io::BufReader::new(File::open(path)?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns the state after `step` steps, rebuilt from the last full state before it.
+    pub fn state(&self, step: usize) -> Grid<char> {
+        let key = (0..=step)
+            .rev()
+            .find(|&i| matches!(self.steps[i], Step::Key(_)))
+            .expect("A trace should start with a full state.");
+        let Step::Key(grid) = &self.steps[key] else {
+            unreachable!();
+        };
+
+        let mut grid = grid.clone();
+        for step in &self.steps[key + 1..=step] {
+            if let Step::Diff(changes) = step {
+                for &(x, y, symbol) in changes {
+                    grid.set(x, y, symbol);
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn color(&self, symbol: char) -> Color {
+        *self.colors.get(&symbol).unwrap_or(&UNKNOWN_COLOR)
+    }
+}
+
+fn print_state(trace: &Trace, step: usize) {
+    let state = trace.state(step);
+    for row in state.rows() {
+        println!("{}", row.iter().collect::<String>());
+    }
+    println!("Step {step} of {}", trace.len() - 1);
+}
+
+/// Replays a trace file, reading commands from the standard input:
+///
+/// - `n [COUNT]` (or an empty line) and `b [COUNT]` step forwards and backwards,
+/// - `g STEP` jumps to a step,
+/// - `p` prints the current state,
+/// - `r PATH [SCALE]` renders the current state to a PNG or PPM image,
+/// - `q` quits.
+pub fn replay(path: &str) -> io::Result<()> {
+    let trace = Trace::load(path)?;
+    if trace.is_empty() {
+        println!("The trace is empty.");
+        return Ok(());
+    }
+
+    let last = trace.len() - 1;
+    let mut step = 0;
+    print_state(&trace, step);
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let mut arguments = line.split_whitespace();
+        let command = arguments.next().unwrap_or("n");
+        let argument = arguments.next();
+        let count = || argument.map_or(Some(1), |count| count.parse::<usize>().ok());
+
+        match (command, count()) {
+            ("n", Some(count)) => step = (step + count).min(last),
+            ("b", Some(count)) => step = step.saturating_sub(count),
+            ("g", _) => match argument.and_then(|value| value.parse::<usize>().ok()) {
+                Some(target) if target <= last => step = target,
+                _ => {
+                    println!("Expected a step from 0 to {last}.");
+                    continue;
+                }
+            },
+            ("p", _) => (),
+            ("r", _) => {
+                let Some(path) = argument else {
+                    println!("Expected the path of the image.");
+                    continue;
+                };
+                let scale = arguments
+                    .next()
+                    .and_then(|scale| scale.parse().ok())
+                    .unwrap_or(4);
+                let image =
+                    Image::from_grid(&trace.state(step), |&symbol| trace.color(symbol), scale);
+                match image.save(path) {
+                    Ok(()) => println!("Saved step {step} to {path}."),
+                    Err(error) => println!("Could not save the image: {error}"),
+                }
+                continue;
+            }
+            ("q", _) => break,
+            _ => {
+                println!("Unknown command. Use n, b, g, p, r or q.");
+                continue;
+            }
+        }
+        print_state(&trace, step);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_trace() {
+    let path = std::env::temp_dir().join("advent_of_code_trace_test.trace");
+    let mut recorder = Recorder {
+        file: BufWriter::new(File::create(&path).unwrap()),
+        symbol: |&cell: &u8| if cell == 0 { ' ' } else { '#' },
+        palette: |&cell: &u8| [cell; 3],
+        colors: HashMap::new(),
+        previous: None,
+        steps: 0,
+    };
+    writeln!(recorder.file, "trace").unwrap();
+
+    let grids: Vec<Grid<u8>> = (0..KEY_INTERVAL + 5)
+        .map(|step| Grid::from_fn(3, 2, |x, y| ((x + y + step) % 2 * 255) as u8))
+        .collect();
+    for grid in &grids {
+        recorder.record(grid);
+    }
+    recorder.record(&Grid::from_fn(1, 1, |_, _| 0));
+    drop(recorder);
+
+    let trace = Trace::load(&path).unwrap();
+    assert_eq!(trace.len(), grids.len() + 1);
+    for step in [0, 1, 2, KEY_INTERVAL - 1, KEY_INTERVAL, KEY_INTERVAL + 4] {
+        let expected = grids[step].map(|&cell| if cell == 0 { ' ' } else { '#' });
+        assert_eq!(trace.state(step), expected);
+    }
+    assert_eq!(trace.state(grids.len()), Grid::from_fn(1, 1, |_, _| ' '));
+    assert_eq!(trace.color('#'), [255; 3]);
+
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(text.lines().nth(6).unwrap().starts_with("diff "));
+    assert!(Trace::parse("trace\ndiff 0,0:#".as_bytes()).is_err());
+    assert!(Trace::parse("trace\nkey 2 1\n#".as_bytes()).is_err());
+}